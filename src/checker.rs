@@ -0,0 +1,624 @@
+use model::Clause;
+use model::ClauseVec;
+use model::Literal;
+use model::LiteralSet;
+use model::LiteralVec;
+use fnv::FnvHashMap;
+use parser::parse_dimacs_clauses;
+use parser::parse_dimacs_num_vars;
+use parser::parse_dimacs_xors;
+use std::fs::File;
+use std::io::Read;
+use std::mem;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CertificateFormat {
+    Drat,
+    Lrat,
+}
+
+/// Checks that `proof_filename` is a DRAT or LRAT certificate of the unsatisfiability of the CNF in
+/// `cnf_filename`. Both the text and the binary encodings are accepted. XOR constraints are turned
+/// into the clauses the solver writes its proofs with, added after every other clause.
+pub fn check_proof_file(cnf_filename: &str, proof_filename: &str, format: CertificateFormat) -> Result<(), String> {
+    let mut clauses = parse_dimacs_clauses(cnf_filename)?;
+    let mut num_vars = clauses.iter().flat_map(|clause| clause.lits().iter().map(|lit| lit.var())).max().unwrap_or(0);
+
+    for xor in parse_dimacs_xors(cnf_filename)? {
+        num_vars = xor.vars().iter().cloned().fold(num_vars, u32::max);
+        clauses.extend(xor.clauses(&mut num_vars).iter().map(|lits| Clause::from_lits(lits)));
+    }
+    let num_vars = num_vars.max(parse_dimacs_num_vars(cnf_filename)?);
+
+    let mut proof = Vec::new();
+    File::open(proof_filename)
+        .and_then(|mut file| file.read_to_end(&mut proof))
+        .map_err(|_err| format!("Cannot read file: {}", proof_filename))?;
+
+    match format {
+        CertificateFormat::Drat => check_drat(&clauses, num_vars, &proof),
+        CertificateFormat::Lrat => check_lrat(&clauses, num_vars, &proof),
+    }
+}
+
+/// Checks a DRAT proof by forward checking: every added clause must be RUP, or RAT on its first
+/// literal, with respect to the clauses that are alive at that point. Proof literals must be over
+/// the `num_vars` variables of the formula, so extended resolution is not supported.
+pub fn check_drat(clauses: &ClauseVec, num_vars: u32, proof: &[u8]) -> Result<(), String> {
+    let mut checker = RupChecker::default();
+    clauses.iter().for_each(|clause| checker.add_clause(clause.lits()));
+
+    for (step_number, step) in parse_drat(proof, num_vars)?.into_iter().enumerate() {
+        match step {
+            DratStep::Add(lits) => {
+                if !checker.is_rup(&lits) && !checker.is_rat(&lits) {
+                    return Err(format!("Step {}: clause {} is neither RUP nor RAT", step_number + 1, format_lits(&lits)));
+                }
+
+                checker.add_clause(&lits);
+            },
+            DratStep::Delete(lits) => checker.delete_clause(&lits),
+        }
+    }
+
+    if checker.is_rup(&[]) {
+        Ok(())
+    } else {
+        Err(String::from("The proof does not derive the empty clause"))
+    }
+}
+
+/// Checks an LRAT proof: every added clause must become falsified by unit propagation over its
+/// hints, taken in order. RAT hints are not supported, and proof literals must be over the
+/// `num_vars` variables of the formula.
+pub fn check_lrat(clauses: &ClauseVec, num_vars: u32, proof: &[u8]) -> Result<(), String> {
+    let mut clause_db: FnvHashMap<u64, LiteralVec> = clauses.iter().enumerate()
+        .map(|(index, clause)| (index as u64 + 1, clause.lits().to_vec()))
+        .collect();
+
+    for step in parse_lrat(proof, num_vars)? {
+        match step {
+            LratStep::Add(clause_id, lits, hints) => {
+                check_lrat_hints(&clause_db, &lits, &hints)
+                    .map_err(|err| format!("Clause {}: {}", clause_id, err))?;
+
+                if lits.is_empty() {
+                    return Ok(());
+                }
+
+                clause_db.insert(clause_id, lits);
+            },
+            LratStep::Delete(clause_ids) => clause_ids.iter().for_each(|clause_id| {
+                clause_db.remove(clause_id);
+            }),
+        }
+    }
+
+    Err(String::from("The proof does not derive the empty clause"))
+}
+
+fn check_lrat_hints(clause_db: &FnvHashMap<u64, LiteralVec>, lits: &[Literal], hints: &[i64]) -> Result<(), String> {
+    let mut assigned_lits: LiteralSet = lits.iter().map(|lit| lit.complementary()).collect();
+
+    for &hint in hints {
+        if hint < 0 {
+            return Err(String::from("RAT hints are not supported"));
+        }
+
+        let hint_lits = clause_db.get(&(hint as u64))
+            .ok_or_else(|| format!("hint {} is not an alive clause", hint))?;
+
+        if hint_lits.iter().any(|lit| assigned_lits.contains(lit)) {
+            return Err(format!("hint {} is satisfied", hint));
+        }
+
+        let unassigned_lits: LiteralVec = hint_lits.iter().cloned()
+            .filter(|lit| !assigned_lits.contains(&lit.complementary()))
+            .collect();
+
+        match unassigned_lits.len() {
+            0 => return Ok(()),
+            1 => { assigned_lits.insert(unassigned_lits[0]); },
+            _ => return Err(format!("hint {} is not unit", hint)),
+        }
+    }
+
+    Err(String::from("hints do not lead to a conflict"))
+}
+
+/// Literal of the proof number `number`, which must be over one of the `num_vars` variables of the
+/// formula.
+fn parse_literal(number: i64, num_vars: u32) -> Result<Literal, String> {
+    if number.unsigned_abs() > u64::from(num_vars.min(i32::MAX as u32)) {
+        return Err(format!("Literal {} is out of range: the formula has {} variables", number, num_vars));
+    }
+
+    Ok(Literal::from(number as i32))
+}
+
+fn parse_literals(numbers: Vec<i64>, num_vars: u32) -> Result<LiteralVec, String> {
+    numbers.into_iter().map(|number| parse_literal(number, num_vars)).collect()
+}
+
+fn format_lits(lits: &[Literal]) -> String {
+    let formatted_lits: Vec<_> = lits.iter().map(|lit| format!("{}", lit)).collect();
+
+    format!("{{{}}}", formatted_lits.join(", "))
+}
+
+/// Clause database checking RUP and RAT by unit propagation over two watched literals, kept in the
+/// first two positions of every clause.
+#[derive(Default)]
+struct RupChecker {
+    clauses: Vec<LiteralVec>,
+    deleted_clauses: Vec<bool>,
+    clause_ids: FnvHashMap<LiteralVec, Vec<usize>>,
+    watched_lit_to_clause: FnvHashMap<Literal, Vec<usize>>,
+    unit_clauses: Vec<usize>,
+    empty_clauses: usize,
+    assigned_lits: LiteralSet,
+    trail: LiteralVec,
+}
+
+impl RupChecker {
+    fn add_clause(&mut self, lits: &[Literal]) {
+        let clause_id = self.clauses.len();
+
+        let mut lits = lits.to_vec();
+        let mut seen_lits = LiteralSet::default();
+        lits.retain(|&lit| seen_lits.insert(lit));
+
+        match lits.len() {
+            0 => self.empty_clauses += 1,
+            1 => self.unit_clauses.push(clause_id),
+            _ => {
+                self.watched_lit_to_clause.entry(lits[0]).or_default().push(clause_id);
+                self.watched_lit_to_clause.entry(lits[1]).or_default().push(clause_id);
+            },
+        }
+
+        self.clause_ids.entry(sorted(&lits)).or_default().push(clause_id);
+        self.clauses.push(lits);
+        self.deleted_clauses.push(false);
+    }
+
+    /// Deletions of clauses that are not in the database are ignored, as DRAT checkers usually do.
+    fn delete_clause(&mut self, lits: &[Literal]) {
+        if let Some(clause_id) = self.clause_ids.get_mut(&sorted(lits)).and_then(|ids| ids.pop()) {
+            self.deleted_clauses[clause_id] = true;
+
+            match lits.len() {
+                0 => self.empty_clauses -= 1,
+                1 => self.unit_clauses.retain(|&id| id != clause_id),
+                _ => (),
+            }
+        }
+    }
+
+    fn is_rup(&mut self, lits: &[Literal]) -> bool {
+        let is_rup = self.propagates_to_conflict(lits);
+        self.undo_assignments();
+
+        is_rup
+    }
+
+    fn is_rat(&mut self, lits: &[Literal]) -> bool {
+        let pivot = match lits.first() {
+            Some(&pivot) => pivot,
+            None => return false,
+        };
+
+        let resolvents: Vec<LiteralVec> = self.clauses.iter().enumerate()
+            .filter(|&(clause_id, clause)| !self.deleted_clauses[clause_id] && clause.contains(&pivot.complementary()))
+            .map(|(_, clause)| {
+                let mut resolvent = lits.to_vec();
+                resolvent.extend(clause.iter().cloned().filter(|&lit| lit != pivot.complementary()));
+                resolvent
+            })
+            .collect();
+
+        resolvents.iter().all(|resolvent| self.is_rup(resolvent))
+    }
+
+    fn propagates_to_conflict(&mut self, lits: &[Literal]) -> bool {
+        if self.empty_clauses > 0 {
+            return true;
+        }
+
+        for lit in lits {
+            if !self.assign(lit.complementary()) {
+                // Tautologies are trivially implied
+                return true;
+            }
+        }
+
+        for index in 0..self.unit_clauses.len() {
+            let unit_lit = self.clauses[self.unit_clauses[index]][0];
+            if !self.assign(unit_lit) {
+                return true;
+            }
+        }
+
+        let mut next_to_propagate = 0;
+        while next_to_propagate < self.trail.len() {
+            let lit = self.trail[next_to_propagate];
+            next_to_propagate += 1;
+
+            if self.propagate(lit.complementary()) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Visits the clauses watching the falsified `lit`, returning whether one of them is in conflict.
+    fn propagate(&mut self, lit: Literal) -> bool {
+        let clause_ids = match self.watched_lit_to_clause.get_mut(&lit) {
+            Some(clause_ids) => mem::take(clause_ids),
+            None => return false,
+        };
+
+        let mut still_watching = Vec::with_capacity(clause_ids.len());
+        let mut conflict = false;
+
+        for (index, &clause_id) in clause_ids.iter().enumerate() {
+            if self.deleted_clauses[clause_id] {
+                continue;
+            }
+
+            let clause = &mut self.clauses[clause_id];
+            if clause[0] == lit {
+                clause.swap(0, 1);
+            }
+
+            if self.assigned_lits.contains(&clause[0]) {
+                still_watching.push(clause_id);
+                continue;
+            }
+
+            let assigned_lits = &self.assigned_lits;
+            match (2..clause.len()).find(|&k| !assigned_lits.contains(&clause[k].complementary())) {
+                Some(k) => {
+                    clause.swap(1, k);
+                    let new_watched_lit = clause[1];
+                    self.watched_lit_to_clause.entry(new_watched_lit).or_default().push(clause_id);
+                },
+                None => {
+                    still_watching.push(clause_id);
+
+                    let unit_lit = clause[0];
+                    if !self.assign(unit_lit) {
+                        conflict = true;
+                        still_watching.extend(clause_ids[index + 1..].iter().cloned()
+                            .filter(|&id| !self.deleted_clauses[id]));
+                        break;
+                    }
+                },
+            }
+        }
+
+        self.watched_lit_to_clause.get_mut(&lit).unwrap().extend(still_watching);
+
+        conflict
+    }
+
+    /// Assigns `lit`, returning false when it is already falsified.
+    fn assign(&mut self, lit: Literal) -> bool {
+        if self.assigned_lits.contains(&lit.complementary()) {
+            return false;
+        }
+
+        if self.assigned_lits.insert(lit) {
+            self.trail.push(lit);
+        }
+
+        true
+    }
+
+    fn undo_assignments(&mut self) {
+        self.assigned_lits.clear();
+        self.trail.clear();
+    }
+}
+
+fn sorted(lits: &[Literal]) -> LiteralVec {
+    let mut sorted_lits = lits.to_vec();
+    sorted_lits.sort();
+    sorted_lits.dedup();
+    sorted_lits
+}
+
+enum DratStep {
+    Add(LiteralVec),
+    Delete(LiteralVec),
+}
+
+enum LratStep {
+    Add(u64, LiteralVec, Vec<i64>),
+    Delete(Vec<u64>),
+}
+
+/// Binary proofs are told apart from text ones by the zero bytes ending each of their steps.
+fn is_binary(proof: &[u8]) -> bool {
+    proof.contains(&0)
+}
+
+fn parse_drat(proof: &[u8], num_vars: u32) -> Result<Vec<DratStep>, String> {
+    if is_binary(proof) {
+        let mut reader = BinaryReader { bytes: proof, position: 0 };
+        let mut steps = Vec::new();
+
+        while let Some(step) = reader.next_byte() {
+            let lits = parse_literals(reader.read_zero_terminated()?, num_vars)?;
+
+            match step {
+                b'a' => steps.push(DratStep::Add(lits)),
+                b'd' => steps.push(DratStep::Delete(lits)),
+                other => return Err(format!("Unexpected binary proof step: {}", other)),
+            }
+        }
+
+        return Ok(steps);
+    }
+
+    text_lines(proof)?.into_iter()
+        .map(|tokens| {
+            let (is_deletion, lits) = match tokens.first().map(|token| token.as_str()) {
+                Some("d") => (true, &tokens[1..]),
+                _ => (false, &tokens[..]),
+            };
+
+            let lits = parse_literals(parse_zero_terminated(lits)?.0, num_vars)?;
+
+            Ok(if is_deletion { DratStep::Delete(lits) } else { DratStep::Add(lits) })
+        })
+        .collect()
+}
+
+fn parse_lrat(proof: &[u8], num_vars: u32) -> Result<Vec<LratStep>, String> {
+    if is_binary(proof) {
+        let mut reader = BinaryReader { bytes: proof, position: 0 };
+        let mut steps = Vec::new();
+
+        while let Some(step) = reader.next_byte() {
+            match step {
+                b'a' => {
+                    let clause_id = reader.read_number()?;
+                    let lits = parse_literals(reader.read_zero_terminated()?, num_vars)?;
+                    let hints = reader.read_zero_terminated()?;
+
+                    steps.push(LratStep::Add(clause_id as u64, lits, hints));
+                },
+                b'd' => {
+                    let clause_ids = reader.read_zero_terminated()?.into_iter().map(|id| id as u64).collect();
+
+                    steps.push(LratStep::Delete(clause_ids));
+                },
+                other => return Err(format!("Unexpected binary proof step: {}", other)),
+            }
+        }
+
+        return Ok(steps);
+    }
+
+    text_lines(proof)?.into_iter()
+        .map(|tokens| {
+            let clause_id = tokens.first().and_then(|token| token.parse::<u64>().ok())
+                .ok_or_else(|| format!("Expected a clause id: {}", tokens.join(" ")))?;
+
+            if tokens.get(1).map(|token| token.as_str()) == Some("d") {
+                let clause_ids = parse_zero_terminated(&tokens[2..])?.0.into_iter().map(|id| id as u64).collect();
+
+                return Ok(LratStep::Delete(clause_ids));
+            }
+
+            let (lits, rest) = parse_zero_terminated(&tokens[1..])?;
+            let (hints, _) = parse_zero_terminated(rest)?;
+
+            Ok(LratStep::Add(clause_id, parse_literals(lits, num_vars)?, hints))
+        })
+        .collect()
+}
+
+/// Tokens of every line that is neither empty nor a comment.
+fn text_lines(proof: &[u8]) -> Result<Vec<Vec<String>>, String> {
+    let content = String::from_utf8(proof.to_vec())
+        .map_err(|_err| String::from("Text proofs must be valid UTF-8"))?;
+
+    Ok(content.lines()
+        .map(|line| line.split_whitespace().map(String::from).collect::<Vec<_>>())
+        .filter(|tokens| !tokens.is_empty() && tokens[0] != "c")
+        .collect())
+}
+
+fn parse_zero_terminated(tokens: &[String]) -> Result<(Vec<i64>, &[String]), String> {
+    let mut numbers = Vec::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+        let number = token.parse::<i64>().map_err(|_err| format!("Invalid number in proof: {}", token))?;
+
+        if number == 0 {
+            return Ok((numbers, &tokens[index + 1..]));
+        }
+        numbers.push(number);
+    }
+
+    Err(format!("Missing terminating 0: {}", tokens.join(" ")))
+}
+
+struct BinaryReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn next_byte(&mut self) -> Option<u8> {
+        let byte = self.bytes.get(self.position).cloned();
+        self.position += 1;
+
+        byte
+    }
+
+    /// Reads a number encoded as `2 * abs + sign`, 7 bits at a time.
+    fn read_number(&mut self) -> Result<i64, String> {
+        let mut encoded: u64 = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.next_byte().ok_or_else(|| String::from("Unexpected end of binary proof"))?;
+            if shift >= 64 {
+                return Err(String::from("Number too large in binary proof"));
+            }
+            encoded |= u64::from(byte & 127) << shift;
+            shift += 7;
+
+            if byte < 128 {
+                break;
+            }
+        }
+
+        let abs = (encoded >> 1) as i64;
+        Ok(if encoded & 1 == 1 { -abs } else { abs })
+    }
+
+    fn read_zero_terminated(&mut self) -> Result<Vec<i64>, String> {
+        let mut numbers = Vec::new();
+
+        loop {
+            match self.read_number()? {
+                0 => return Ok(numbers),
+                number => numbers.push(number),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse_dimacs_file;
+    use proof::Proof;
+    use proof::ProofFormat;
+    use solver::Solver;
+    use std::env;
+    use std::fs;
+
+    fn clauses(dimacs_clauses: &[&[i32]]) -> ClauseVec {
+        dimacs_clauses.iter()
+            .map(|dimacs_lits| {
+                let lits: LiteralVec = dimacs_lits.iter().map(|&lit| Literal::from(lit)).collect();
+                Clause::from_lits(&lits)
+            })
+            .collect()
+    }
+
+    /// Every assignment of two variables, UNSAT without any unit clause to propagate.
+    fn unsat_clauses() -> ClauseVec {
+        clauses(&[&[1, 2], &[1, -2], &[-1, 2], &[-1, -2]])
+    }
+
+    #[test]
+    fn drat_accepts_rup_proof() {
+        assert_eq!(check_drat(&unsat_clauses(), 2, b"1 0\n0\n"), Ok(()));
+    }
+
+    #[test]
+    fn drat_rejects_lemma_neither_rup_nor_rat() {
+        let result = check_drat(&clauses(&[&[1, 2], &[-1, 2]]), 2, b"-2 0\n0\n");
+
+        assert!(result.unwrap_err().contains("neither RUP nor RAT"));
+    }
+
+    #[test]
+    fn drat_accepts_rat_lemma() {
+        // 3 occurs nowhere else, so the lemma is RAT on it without being RUP
+        let mut checker = RupChecker::default();
+        unsat_clauses().iter().for_each(|clause| checker.add_clause(clause.lits()));
+        assert!(!checker.is_rup(&[Literal::from(3)]));
+        assert!(checker.is_rat(&[Literal::from(3)]));
+
+        assert_eq!(check_drat(&unsat_clauses(), 3, b"3 0\n1 0\n0\n"), Ok(()));
+    }
+
+    #[test]
+    fn drat_rejects_proof_without_empty_clause() {
+        let result = check_drat(&unsat_clauses(), 2, b"1 2 0\n");
+
+        assert_eq!(result, Err(String::from("The proof does not derive the empty clause")));
+    }
+
+    #[test]
+    fn drat_parses_binary_proof() {
+        let clauses = clauses(&[&[1, 100], &[1, -100], &[-1, 100], &[-1, -100]]);
+        // Adds 100, encoded on two bytes, deletes -1 100, then adds the empty clause
+        let proof = [b'a', 0xc8, 0x01, 0, b'd', 3, 0xc8, 0x01, 0, b'a', 0];
+
+        assert_eq!(check_drat(&clauses, 100, &proof), Ok(()));
+    }
+
+    #[test]
+    fn drat_binary_proof_is_checked() {
+        // Adds -1, which is not implied
+        let proof = [b'a', 3, 0, b'a', 0];
+
+        assert!(check_drat(&clauses(&[&[1, 2], &[1, -2]]), 2, &proof).is_err());
+    }
+
+    #[test]
+    fn lrat_accepts_valid_hints() {
+        assert_eq!(check_lrat(&unsat_clauses(), 2, b"5 1 0 1 2 0\n6 0 5 3 4 0\n"), Ok(()));
+    }
+
+    #[test]
+    fn lrat_rejects_wrong_hint() {
+        // Clause 3 is satisfied once 1 is false
+        let result = check_lrat(&unsat_clauses(), 2, b"5 1 0 3 4 0\n6 0 5 3 4 0\n");
+
+        assert!(result.unwrap_err().starts_with("Clause 5:"));
+    }
+
+    #[test]
+    fn out_of_range_literals_are_rejected() {
+        let err = |number: i64| Err(format!("Literal {} is out of range: the formula has 2 variables", number));
+
+        assert_eq!(check_drat(&unsat_clauses(), 2, b"3 0\n0\n"), err(3));
+        assert_eq!(check_drat(&unsat_clauses(), 2, b"4294967296 0\n0\n"), err(4294967296));
+        assert_eq!(check_drat(&unsat_clauses(), 2, b"1 -9223372036854775808 0\n0\n"), err(i64::MIN));
+        assert_eq!(check_drat(&unsat_clauses(), 2, &[b'a', 0x81, 0x80, 0x80, 0x80, 0x20, 0]), err(-4294967296));
+        assert_eq!(check_lrat(&unsat_clauses(), 2, b"5 -3 0 1 2 0\n"), err(-3));
+    }
+
+    #[test]
+    fn overlong_binary_numbers_are_rejected() {
+        let proof = [b'a', 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 0];
+
+        assert_eq!(check_drat(&unsat_clauses(), 2, &proof), Err(String::from("Number too large in binary proof")));
+    }
+
+    #[test]
+    fn proofs_of_formulas_with_long_xors_are_checked() {
+        let xor_lits: Vec<String> = (2..=70).map(|var| var.to_string()).collect();
+        let cnf = format!("p cnf 70 1\n1 0\nx1 {} 0\nx{} 0\n", xor_lits.join(" "), xor_lits.join(" "));
+        let directory = env::temp_dir();
+        let cnf_filename = directory.join(format!("cnsat-xors-{}.cnf", std::process::id()));
+        let proof_filename = directory.join(format!("cnsat-xors-{}.drat", std::process::id()));
+        let cnf_filename = cnf_filename.to_str().unwrap();
+        let proof_filename = proof_filename.to_str().unwrap();
+        fs::write(cnf_filename, cnf).unwrap();
+
+        // As the command line solver does
+        let mut solver = Solver::new();
+        solver.set_proof(Proof::create(proof_filename, ProofFormat::Text).unwrap());
+        parse_dimacs_file(cnf_filename).unwrap().into_iter().for_each(|clause| solver.add_clause(clause));
+        parse_dimacs_xors(cnf_filename).unwrap().into_iter().for_each(|xor| solver.add_xor(xor));
+        assert_eq!(solver.solve(), None);
+        solver.take_proof().unwrap().finish().unwrap();
+
+        let result = check_proof_file(cnf_filename, proof_filename, CertificateFormat::Drat);
+        fs::remove_file(cnf_filename).unwrap();
+        fs::remove_file(proof_filename).unwrap();
+
+        assert_eq!(result, Ok(()));
+    }
+}
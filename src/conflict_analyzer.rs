@@ -1,46 +1,227 @@
-use model::Decision;
-use model::ClauseVec;
+use model::Trail;
+use model::ClauseDb;
 use std::collections::VecDeque;
+use model::LiteralVec;
 use model::Clause;
 use model::LiteralSet;
+use model::Literal;
+use model::ClauseRef;
+use model::Reason;
+use fnv::FnvHashSet;
 
-pub fn learn_from_conflict(decision: &Decision, clause_db: &ClauseVec) -> Clause {
-    let mut terminal_lits = LiteralSet::default();
-    let mut to_explore = VecDeque::with_capacity(decision.propagated_lits_len());
+/// Clause learnt from a conflict, along with the level the solver must backjump to.
+pub struct LearntClause {
+    pub clause: Clause,
+    /// Highest decision level among the literals of `clause` other than its asserting one: the one
+    /// at which `clause` becomes unit.
+    pub backjump_level: u32,
+    /// Clauses of the clause database resolved to learn `clause`.
+    pub antecedents: Vec<ClauseRef>,
+    /// Variables of the resolved clauses, not assigned at level 0.
+    pub involved_vars: Vec<u32>,
+}
+
+/**
+ * First-UIP conflict analysis. The conflicting clause, falsified by the current assignments, is
+ * resolved with the implying clauses of the literals of the conflict level, latest assigned first,
+ * until a single literal of that level is left: the first unique implication point, whose
+ * complementary becomes the asserting literal of the learnt clause. Literals implied by other
+ * literals of the learnt clause are then removed.
+ *
+ * The conflict level must be the current level of `trail`, and hold at least two literals of the
+ * conflicting clause.
+ *
+ * see https://doi.org/10.1109/ICCAD.2001.968634
+ * see https://doi.org/10.1007/978-3-642-02777-2_23
+ */
+pub fn learn_from_conflict(conflict: Reason, trail: &Trail, clause_db: &ClauseDb) -> LearntClause {
+    let conflict_level = trail.decision_level();
+
+    let mut antecedents = Vec::new();
+    let mut involved_vars = Vec::new();
+    let mut learnt_lits = LiteralVec::new();
+    let mut seen_vars = FnvHashSet::default();
+    let mut pending_vars = FnvHashSet::default();
+    let mut assigned_lits = trail.lits().iter().rev();
+
+    let mut reason = conflict;
+
+    let uip = loop {
+        antecedents.extend(reason.clause_ref());
+
+        for &lit in reason.lits(clause_db) {
+            if !seen_vars.insert(lit.var()) {
+                continue;
+            }
+
+            // Literals falsified at level 0 are falsified no matter what
+            let level = trail.level(lit.var());
+            if level == 0 {
+                continue;
+            }
+
+            involved_vars.push(lit.var());
+            if level == conflict_level {
+                pending_vars.insert(lit.var());
+            } else {
+                learnt_lits.push(lit);
+            }
+        }
+
+        let lit = *assigned_lits.find(|lit| pending_vars.remove(&lit.var()))
+            .expect("Literals of the conflict level must be on the trail");
+
+        if pending_vars.is_empty() {
+            break lit;
+        }
+        reason = trail.reason(lit.var()).expect("Only propagated literals can be resolved");
+    };
+
+    minimize(&mut learnt_lits, trail, clause_db);
+
+    // The asserting literal and the one of the backjump level are the ones to watch
+    learnt_lits.insert(0, uip.complementary());
+    let backjump_position = (1..learnt_lits.len())
+        .max_by_key(|&position| trail.level(learnt_lits[position].var()));
+
+    let backjump_level = match backjump_position {
+        Some(position) => {
+            learnt_lits.swap(1, position);
+            trail.level(learnt_lits[1].var())
+        },
+        None => 0,
+    };
+
+    let lbd = lbd(&learnt_lits, trail);
+
+    LearntClause {
+        clause: Clause::new_asserting_clause(learnt_lits, lbd),
+        backjump_level,
+        antecedents,
+        involved_vars,
+    }
+}
+
+/// Recursive minimization: removes the literals of `learnt_lits` whose complementary is implied by
+/// the complementaries of the other ones. Implication chains are only followed through the levels
+/// of `learnt_lits`, since the others cannot lead back to them.
+fn minimize(learnt_lits: &mut LiteralVec, trail: &Trail, clause_db: &ClauseDb) {
+    let learnt_levels: FnvHashSet<u32> = learnt_lits.iter().map(|lit| trail.level(lit.var())).collect();
+    // Variables of the learnt clause, then the ones known to be implied by it
+    let mut implied_vars: FnvHashSet<u32> = learnt_lits.iter().map(|lit| lit.var()).collect();
+
+    learnt_lits.retain(|&learnt_lit| {
+        if trail.reason(learnt_lit.var()).is_none() {
+            return true;
+        }
+
+        let mut to_explore = vec![learnt_lit];
+        let mut explored_vars = Vec::new();
+
+        while let Some(lit) = to_explore.pop() {
+            let reason = trail.reason(lit.var()).unwrap();
 
-    let conflict_lit = decision.get_conflict_lit().expect("Decision must contain a conflict!");
-    to_explore.push_back(conflict_lit.complementary());
-    to_explore.push_back(conflict_lit);
+            for &implying_lit in reason.lits(clause_db) {
+                let level = trail.level(implying_lit.var());
 
-    while to_explore.len() > 1 {
-        let lit = to_explore.pop_front().unwrap();
+                if implying_lit.var() == lit.var() || level == 0 || implied_vars.contains(&implying_lit.var()) {
+                    continue;
+                }
 
-        match decision.implying_clause_of(lit) {
-            Some(implying_clause_id) => {
-                clause_db.get(implying_clause_id).unwrap().lits().iter()
+                if trail.reason(implying_lit.var()).is_none() || !learnt_levels.contains(&level) {
+                    explored_vars.iter().for_each(|var| { implied_vars.remove(var); });
+                    return true;
+                }
+
+                implied_vars.insert(implying_lit.var());
+                explored_vars.push(implying_lit.var());
+                to_explore.push(implying_lit);
+            }
+        }
+
+        false
+    });
+}
+
+/// Literal block distance of falsified `lits`: the number of decision levels among them.
+pub fn lbd(lits: &[Literal], trail: &Trail) -> u32 {
+    let lit_levels: FnvHashSet<u32> = lits.iter()
+        .map(|lit| trail.level(lit.var()))
+        .collect();
+
+    lit_levels.len() as u32
+}
+
+/// Traces the falsified `assumption` back through the implication graph of `trail` to the
+/// assumptions implying its complementary. Only assumptions have been decided at that point, so
+/// every decision reached is one of them. Literals implied at level 0 hold no matter what was
+/// assumed and are left out.
+pub fn explain_failed_assumption(assumption: Literal, trail: &Trail, clause_db: &ClauseDb) -> LiteralSet {
+    let mut failed_assumptions = LiteralSet::default();
+    let mut explored_lits = LiteralSet::default();
+    let mut to_explore = VecDeque::new();
+
+    failed_assumptions.insert(assumption);
+    to_explore.push_back(assumption.complementary());
+
+    while let Some(lit) = to_explore.pop_front() {
+        if !explored_lits.insert(lit) {
+            continue;
+        }
+
+        if trail.level(lit.var()) == 0 {
+            continue;
+        }
+
+        match trail.reason(lit.var()) {
+            Some(reason) => {
+                reason.lits(clause_db).iter()
                     .filter(|clause_lit| **clause_lit != lit)
                     .map(|clause_lit| clause_lit.complementary())
-                    .for_each(|implying_lit| {
-                        if !to_explore.contains(&implying_lit) {
-                            to_explore.push_back(implying_lit);
-                        }
-                    });
+                    .for_each(|implying_lit| to_explore.push_back(implying_lit));
             },
-            None => { terminal_lits.insert(lit.complementary()); },
+            None => { failed_assumptions.insert(lit); },
         }
     }
 
-    let decision_lit = decision.lit().complementary();
-    let last_lit = if terminal_lits.contains(&decision_lit) {
-        decision_lit
-    } else {
-        let last_lit = to_explore.pop_front().unwrap().complementary();
-        terminal_lits.insert(last_lit);
-        last_lit
-    };
-
-    // Learnt clause
-    Clause::new_asserting_clause(last_lit, terminal_lits.iter().cloned().collect())
+    failed_assumptions
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_assumption_is_explained_by_the_assumptions_implying_its_complementary() {
+        let lit = Literal::from;
+        let mut clause_db = ClauseDb::new();
+        let clause_ref = clause_db.add(&Clause::from_lits(&[lit(-4), lit(-1), lit(-6), lit(-5)]), false);
+        let mut trail = Trail::new();
+
+        trail.assign(lit(5), 0, None);
+        for (level, &assumption) in [1, 2, 3].iter().enumerate() {
+            trail.new_decision_level();
+            trail.assign(lit(assumption), level as u32 + 1, None);
+        }
+        trail.assign(lit(6), 3, Some(Reason::Binary([lit(6), lit(-3)])));
+        trail.assign(lit(-4), 3, Some(Reason::Clause(clause_ref)));
+
+        let failed_assumptions = explain_failed_assumption(lit(4), &trail, &clause_db);
 
+        assert_eq!(failed_assumptions, [lit(4), lit(1), lit(3)].iter().cloned().collect());
+    }
+
+    #[test]
+    fn assumption_falsified_at_level_0_fails_alone() {
+        let lit = Literal::from;
+        let mut clause_db = ClauseDb::new();
+        let clause_ref = clause_db.add(&Clause::from_lits(&[lit(-2), lit(-1)]), false);
+        let mut trail = Trail::new();
+        trail.assign(lit(1), 0, None);
+        trail.assign(lit(-2), 0, Some(Reason::Clause(clause_ref)));
+
+        let failed_assumptions = explain_failed_assumption(lit(2), &trail, &clause_db);
+
+        assert_eq!(failed_assumptions, [lit(2)].iter().cloned().collect());
+    }
+}
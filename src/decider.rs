@@ -1,112 +1,774 @@
 use model::Clause;
 use model::Literal;
-use fnv::FnvHashMap;
-use std::collections::BTreeMap;
-use model::LiteralSet;
+use random::Random;
+
+const ACTIVITY_LIMIT: f64 = 1e100;
+// Step size of the exponential moving averages of CHB and LRB, which decreases after every conflict
+const INITIAL_STEP_SIZE: f64 = 0.4;
+const STEP_SIZE_DECREMENT: f64 = 1e-6;
+const MIN_STEP_SIZE: f64 = 0.06;
+// Decay of the LRB score of a variable for every conflict it stays unassigned through
+const LRB_UNASSIGNED_DECAY: f64 = 0.95;
+
+/// Picks the variable the solver decides next, scoring variables from the conflicts they are
+/// involved in. Scores survive restarts.
+pub trait Decider {
+    /// Makes `var` a candidate for decisions.
+    fn add_var(&mut self, var: u32);
+
+    /// Unassigned variable with the best score, if any is left.
+    fn next_variable(&mut self) -> Option<u32>;
+
+    fn assign_lit(&mut self, lit: Literal);
+
+    fn un_assign_lit(&mut self, lit: Literal);
+
+    /// Called after every conflict a clause is learnt from, with the variables involved in it.
+    fn on_conflict(&mut self, vars: &[u32]);
+
+    /// Breaks ties between variables of equal scores at random, seeding the generator random
+    /// decisions are also taken with. Without a seed, ties are broken by variable order, the lowest
+    /// variable first.
+    fn set_seed(&mut self, seed: u64);
+
+    /// Decides a variable picked at random, instead of the best one, for the given fraction of
+    /// the decisions, 0 by default.
+    fn set_random_decision_fraction(&mut self, fraction: f64);
+
+    /// Called with every clause of the formula, not with learnt ones. Makes its variables
+    /// candidates for decisions.
+    fn add_clause(&mut self, clause: &Clause) {
+        clause.lits().iter().for_each(|lit| self.add_var(lit.var()));
+    }
+}
+
+/// Builds the decider called `name`: one of `vsids`, `vmtf`, `chb` or `lrb`.
+pub fn decider_from_name(name: &str) -> Result<Box<dyn Decider>, String> {
+    match name {
+        "vsids" => Ok(Box::new(VSIDSDecider::new())),
+        "vmtf" => Ok(Box::new(VMTFDecider::new())),
+        "chb" => Ok(Box::new(CHBDecider::new())),
+        "lrb" => Ok(Box::new(LRBDecider::new())),
+        _ => Err(format!("Unknown decider: {}", name)),
+    }
+}
 
 /**
- * A next literal generator based on VSIDS.
+ * A next variable generator based on VSIDS, in its exponential flavour (EVSIDS): the variables
+ * involved in a conflict get their activity bumped by an increment that grows after every conflict,
+ * which amounts to decaying every other activity. Unassigned variables are kept in a heap ordered
+ * by activity. The polarity a variable is decided with is chosen separately.
+ *
+ * Clauses of the formula bump their variables too, by their Jeroslow-Wang weight `2^-n` for `n`
+ * literals, so that the variables occurring the most in short clauses are decided first until
+ * conflicts take over.
  *
  * see https://www.princeton.edu/~chaff/publication/DAC2001v56.pdf
+ * see https://doi.org/10.1007/978-3-540-24605-3_37
+ * see https://doi.org/10.1007/BF01531077
  */
 pub struct VSIDSDecider {
-    lit_count: FnvHashMap<Literal, u64>,
-    count_lit: BTreeMap<u64, LiteralSet>,
-    unassigned_lits: LiteralSet,
-    age: u64,
+    activities: Vec<f64>,
+    assigned: Vec<bool>,
+    heap: VarHeap,
+    increment: f64,
+    decay: f64,
+    randomness: Randomness,
 }
 
 impl VSIDSDecider {
     pub fn new() -> Self {
         VSIDSDecider {
-            lit_count: FnvHashMap::default(),
-            count_lit: BTreeMap::new(),
-            unassigned_lits: LiteralSet::default(),
-            age: 0,
+            activities: Vec::new(),
+            assigned: Vec::new(),
+            heap: VarHeap::new(),
+            increment: 1.0,
+            decay: 0.95,
+            randomness: Randomness::new(),
         }
     }
 
-    pub fn add_clause(&mut self, clause: &Clause) {
-        let increment = 1 + self.age_factor();
+    fn bump(&mut self, var: u32, increment: f64) {
+        self.activities[var as usize] += increment;
 
-        clause.lits().iter().cloned().for_each(|lit| {
-            let previous_count = self.lit_count.get(&lit).cloned();
+        if self.activities[var as usize] > ACTIVITY_LIMIT {
+            self.activities.iter_mut().for_each(|activity| *activity /= ACTIVITY_LIMIT);
+            self.increment /= ACTIVITY_LIMIT;
+        }
 
-            // Increment lit_count
-            let current_count = self.lit_count.entry(lit)
-                .and_modify(|count| *count += increment)
-                .or_insert(increment).clone();
+        self.heap.update(var, &self.activities);
+    }
+}
 
-            // Remove lit from previous count_lit
-            let mut delete_previous_entry = false;
-            if previous_count.is_some() {
-                let previous_count = previous_count.unwrap();
-                let previous_entry = self.count_lit.entry(previous_count);
+impl Default for VSIDSDecider {
+    fn default() -> Self {
+        VSIDSDecider::new()
+    }
+}
 
-                let modified_previous_set = previous_entry
-                    .and_modify(|lits| { lits.remove(&lit); })
-                    .or_default();
+impl Decider for VSIDSDecider {
+    fn add_var(&mut self, var: u32) {
+        if var as usize >= self.activities.len() {
+            let first_new_var = self.activities.len().max(1) as u32;
 
-                if modified_previous_set.is_empty() {
-                    delete_previous_entry = true;
-                }
+            self.activities.resize(var as usize + 1, 0.0);
+            self.assigned.resize(var as usize + 1, false);
+            for new_var in first_new_var..=var {
+                self.heap.set_tie_breaker(new_var, self.randomness.tie_breaker(new_var), &self.activities);
+                self.heap.push(new_var, &self.activities);
             }
+        }
+    }
+
+    fn next_variable(&mut self) -> Option<u32> {
+        self.randomness.random_var(&self.assigned)
+            .or_else(|| self.heap.pop_unassigned(&self.activities, &self.assigned))
+    }
+
+    #[inline]
+    fn assign_lit(&mut self, lit: Literal) {
+        self.add_var(lit.var());
+        self.assigned[lit.var() as usize] = true;
+    }
+
+    #[inline]
+    fn un_assign_lit(&mut self, lit: Literal) {
+        self.assigned[lit.var() as usize] = false;
+        self.heap.push(lit.var(), &self.activities);
+    }
+
+    /// Bumps the activity of the variables involved in a conflict, then decays every activity.
+    fn on_conflict(&mut self, vars: &[u32]) {
+        for &var in vars {
+            self.bump(var, self.increment);
+        }
+
+        self.increment /= self.decay;
+    }
+
+    fn add_clause(&mut self, clause: &Clause) {
+        let weight = 0.5f64.powi(clause.lits().len().min(i32::MAX as usize) as i32);
+
+        for lit in clause.lits() {
+            self.add_var(lit.var());
+            self.bump(lit.var(), weight * self.increment);
+        }
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.randomness.seed(seed);
+
+        for var in 1..self.activities.len() as u32 {
+            self.heap.set_tie_breaker(var, self.randomness.tie_breaker(var), &self.activities);
+        }
+    }
+
+    fn set_random_decision_fraction(&mut self, fraction: f64) {
+        self.randomness.decision_fraction = fraction;
+    }
+}
+
+/**
+ * Variable move-to-front: variables are queued in the order they were last involved in a conflict,
+ * and the most recent unassigned one is decided next. A search pointer remembers where the last
+ * unassigned variable was found, every variable queued after it being assigned. New variables are
+ * queued so that the lowest is decided first, or in random order once seeded.
+ *
+ * see https://doi.org/10.1007/978-3-319-24318-4_29
+ */
+pub struct VMTFDecider {
+    // Queue as a doubly linked list over variables, 0 standing for none
+    previous: Vec<u32>,
+    next: Vec<u32>,
+    first: u32,
+    last: u32,
+    search: u32,
+    // Time every variable was last moved to the front of the queue
+    stamps: Vec<u64>,
+    stamp: u64,
+    // Variables added since the last time the queue was used
+    new_vars: Vec<u32>,
+    assigned: Vec<bool>,
+    randomness: Randomness,
+}
+
+impl VMTFDecider {
+    pub fn new() -> Self {
+        VMTFDecider {
+            previous: Vec::new(),
+            next: Vec::new(),
+            first: 0,
+            last: 0,
+            search: 0,
+            stamps: Vec::new(),
+            stamp: 0,
+            new_vars: Vec::new(),
+            assigned: Vec::new(),
+            randomness: Randomness::new(),
+        }
+    }
+
+    /// Queues the variables added since the last call, shuffled once seeded, else in decreasing
+    /// order since the last one queued is decided first.
+    fn enqueue_new_vars(&mut self) {
+        if self.new_vars.is_empty() {
+            return;
+        }
+
+        let mut new_vars = self.new_vars.split_off(0);
+        if self.randomness.seeded {
+            self.randomness.random.shuffle(&mut new_vars);
+        } else {
+            new_vars.reverse();
+        }
+
+        new_vars.into_iter().for_each(|var| self.enqueue(var));
+        self.search = self.last;
+    }
+
+    fn enqueue(&mut self, var: u32) {
+        self.previous[var as usize] = self.last;
+        self.next[var as usize] = 0;
+
+        if self.last == 0 {
+            self.first = var;
+        } else {
+            self.next[self.last as usize] = var;
+        }
+        self.last = var;
 
-            if delete_previous_entry {
-                self.count_lit.remove(&previous_count.unwrap());
+        self.stamp += 1;
+        self.stamps[var as usize] = self.stamp;
+    }
+
+    fn dequeue(&mut self, var: u32) {
+        let previous = self.previous[var as usize];
+        let next = self.next[var as usize];
+
+        if previous == 0 {
+            self.first = next;
+        } else {
+            self.next[previous as usize] = next;
+        }
+
+        if next == 0 {
+            self.last = previous;
+        } else {
+            self.previous[next as usize] = previous;
+        }
+    }
+}
+
+impl Default for VMTFDecider {
+    fn default() -> Self {
+        VMTFDecider::new()
+    }
+}
+
+impl Decider for VMTFDecider {
+    fn add_var(&mut self, var: u32) {
+        if var as usize >= self.stamps.len() {
+            let first_new_var = self.stamps.len().max(1) as u32;
+
+            self.previous.resize(var as usize + 1, 0);
+            self.next.resize(var as usize + 1, 0);
+            self.stamps.resize(var as usize + 1, 0);
+            self.assigned.resize(var as usize + 1, false);
+            self.new_vars.extend(first_new_var..=var);
+        }
+    }
+
+    fn next_variable(&mut self) -> Option<u32> {
+        self.enqueue_new_vars();
+
+        if let Some(var) = self.randomness.random_var(&self.assigned) {
+            return Some(var);
+        }
+
+        let mut var = self.search;
+        while var != 0 && self.assigned[var as usize] {
+            var = self.previous[var as usize];
+        }
+
+        if var == 0 {
+            return None;
+        }
+
+        self.search = var;
+        Some(var)
+    }
+
+    #[inline]
+    fn assign_lit(&mut self, lit: Literal) {
+        self.add_var(lit.var());
+        self.assigned[lit.var() as usize] = true;
+    }
+
+    #[inline]
+    fn un_assign_lit(&mut self, lit: Literal) {
+        let var = lit.var();
+        self.assigned[var as usize] = false;
+
+        if self.stamps[var as usize] > self.stamps[self.search as usize] {
+            self.search = var;
+        }
+    }
+
+    /// Moves the variables involved in a conflict to the front of the queue, keeping their
+    /// relative order.
+    fn on_conflict(&mut self, vars: &[u32]) {
+        self.enqueue_new_vars();
+
+        let mut bumped_vars = vars.to_vec();
+        bumped_vars.sort_by_key(|&var| self.stamps[var as usize]);
+
+        for var in bumped_vars {
+            self.dequeue(var);
+            self.enqueue(var);
+
+            if !self.assigned[var as usize] {
+                self.search = var;
             }
+        }
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.randomness.seed(seed);
+    }
+
+    fn set_random_decision_fraction(&mut self, fraction: f64) {
+        self.randomness.decision_fraction = fraction;
+    }
+}
+
+/**
+ * Conflict history-based branching: the score of a variable is an exponential moving average of
+ * rewards it gets every time it is assigned, which are higher when it was recently involved in a
+ * conflict, and higher still when the propagation that assigned it ended in a conflict.
+ *
+ * see https://doi.org/10.1609/aaai.v30i1.10439
+ */
+pub struct CHBDecider {
+    scores: Vec<f64>,
+    // Conflict every variable was last involved in
+    last_conflicts: Vec<u64>,
+    assigned: Vec<bool>,
+    heap: VarHeap,
+    // Variables assigned since the last decision or conflict
+    assigned_vars: Vec<u32>,
+    conflicts: u64,
+    step_size: f64,
+    randomness: Randomness,
+}
 
-            // Add lit to current count_lit
-            self.count_lit.entry(current_count)
-                .and_modify(|lits| { lits.insert(lit); })
-                .or_insert_with(|| {
-                    let mut lits = LiteralSet::default();
-                    lits.insert(lit);
-                    lits
-                });
-        });
-    }
-
-    pub fn next_literal(&mut self) -> Option<Literal> {
-        if self.unassigned_lits.is_empty() {
-            if self.age != 0 {
-                return None;
+impl CHBDecider {
+    pub fn new() -> Self {
+        CHBDecider {
+            scores: Vec::new(),
+            last_conflicts: Vec::new(),
+            assigned: Vec::new(),
+            heap: VarHeap::new(),
+            assigned_vars: Vec::new(),
+            conflicts: 0,
+            step_size: INITIAL_STEP_SIZE,
+            randomness: Randomness::new(),
+        }
+    }
+
+    /// Rewards the variables assigned by the last propagation.
+    fn reward_assigned_vars(&mut self, multiplier: f64) {
+        for var in self.assigned_vars.drain(..) {
+            let reward = multiplier / (self.conflicts - self.last_conflicts[var as usize] + 1) as f64;
+            let score = &mut self.scores[var as usize];
+            *score += self.step_size * (reward - *score);
+
+            self.heap.update(var, &self.scores);
+        }
+    }
+}
+
+impl Default for CHBDecider {
+    fn default() -> Self {
+        CHBDecider::new()
+    }
+}
+
+impl Decider for CHBDecider {
+    fn add_var(&mut self, var: u32) {
+        if var as usize >= self.scores.len() {
+            let first_new_var = self.scores.len().max(1) as u32;
+
+            self.scores.resize(var as usize + 1, 0.0);
+            self.last_conflicts.resize(var as usize + 1, 0);
+            self.assigned.resize(var as usize + 1, false);
+            for new_var in first_new_var..=var {
+                self.heap.set_tie_breaker(new_var, self.randomness.tie_breaker(new_var), &self.scores);
+                self.heap.push(new_var, &self.scores);
             }
+        }
+    }
+
+    fn next_variable(&mut self) -> Option<u32> {
+        // The last propagation did not end in a conflict
+        self.reward_assigned_vars(0.9);
 
-            // Initialize unassigned_lits
-            self.unassigned_lits = self.lit_count.keys().cloned().collect();
+        self.randomness.random_var(&self.assigned)
+            .or_else(|| self.heap.pop_unassigned(&self.scores, &self.assigned))
+    }
+
+    #[inline]
+    fn assign_lit(&mut self, lit: Literal) {
+        self.add_var(lit.var());
+        self.assigned[lit.var() as usize] = true;
+        self.assigned_vars.push(lit.var());
+    }
+
+    #[inline]
+    fn un_assign_lit(&mut self, lit: Literal) {
+        self.assigned[lit.var() as usize] = false;
+        self.heap.push(lit.var(), &self.scores);
+    }
+
+    fn on_conflict(&mut self, vars: &[u32]) {
+        self.conflicts += 1;
+        vars.iter().for_each(|&var| self.last_conflicts[var as usize] = self.conflicts);
+
+        self.reward_assigned_vars(1.0);
+        self.step_size = (self.step_size - STEP_SIZE_DECREMENT).max(MIN_STEP_SIZE);
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.randomness.seed(seed);
+
+        for var in 1..self.scores.len() as u32 {
+            self.heap.set_tie_breaker(var, self.randomness.tie_breaker(var), &self.scores);
+        }
+    }
+
+    fn set_random_decision_fraction(&mut self, fraction: f64) {
+        self.randomness.decision_fraction = fraction;
+    }
+}
+
+/**
+ * Learning-rate based branching: the score of a variable is an exponential moving average of its
+ * learning rate, the share of the conflicts it was involved in while it was assigned. It is updated
+ * every time the variable is unassigned. Scores also decay for every conflict the variable stays
+ * unassigned through, so that the search focuses on the variables recently assigned.
+ *
+ * see https://doi.org/10.1007/978-3-319-40970-2_9
+ */
+pub struct LRBDecider {
+    scores: Vec<f64>,
+    // Conflicts counted when every variable was last assigned
+    assigned_at: Vec<u64>,
+    // Conflicts every variable was involved in since it was last assigned
+    participations: Vec<u64>,
+    // Conflicts counted when the score of every unassigned variable was last decayed
+    decayed_at: Vec<u64>,
+    assigned: Vec<bool>,
+    heap: VarHeap,
+    conflicts: u64,
+    step_size: f64,
+    randomness: Randomness,
+}
+
+impl LRBDecider {
+    pub fn new() -> Self {
+        LRBDecider {
+            scores: Vec::new(),
+            assigned_at: Vec::new(),
+            participations: Vec::new(),
+            decayed_at: Vec::new(),
+            assigned: Vec::new(),
+            heap: VarHeap::new(),
+            conflicts: 0,
+            step_size: INITIAL_STEP_SIZE,
+            randomness: Randomness::new(),
+        }
+    }
+}
+
+impl Default for LRBDecider {
+    fn default() -> Self {
+        LRBDecider::new()
+    }
+}
+
+impl Decider for LRBDecider {
+    fn add_var(&mut self, var: u32) {
+        if var as usize >= self.scores.len() {
+            let first_new_var = self.scores.len().max(1) as u32;
+
+            self.scores.resize(var as usize + 1, 0.0);
+            self.assigned_at.resize(var as usize + 1, 0);
+            self.participations.resize(var as usize + 1, 0);
+            self.decayed_at.resize(var as usize + 1, 0);
+            self.assigned.resize(var as usize + 1, false);
+            for new_var in first_new_var..=var {
+                self.heap.set_tie_breaker(new_var, self.randomness.tie_breaker(new_var), &self.scores);
+                self.heap.push(new_var, &self.scores);
+            }
+        }
+    }
+
+    /// Scores of unassigned variables are decayed lazily, once they reach the top of the heap.
+    fn next_variable(&mut self) -> Option<u32> {
+        if let Some(var) = self.randomness.random_var(&self.assigned) {
+            return Some(var);
         }
 
-        self.age += 1;
+        while let Some(var) = self.heap.peek() {
+            let unassigned_conflicts = self.conflicts - self.decayed_at[var as usize];
+
+            if self.assigned[var as usize] || unassigned_conflicts == 0 {
+                self.heap.pop(&self.scores);
+
+                if !self.assigned[var as usize] {
+                    return Some(var);
+                }
+                continue;
+            }
+
+            self.scores[var as usize] *= LRB_UNASSIGNED_DECAY.powi(unassigned_conflicts.min(i32::MAX as u64) as i32);
+            self.decayed_at[var as usize] = self.conflicts;
+            self.heap.update(var, &self.scores);
+        }
 
-        self.count_lit.values().rev()
-            .filter(|&lits| self.contains_unassigned_literal(lits))
-            .map(|lits| {
-                lits.iter().cloned()
-                    .filter(|lit| self.unassigned_lits.contains(lit))
-                    .collect::<Vec<_>>()
-            })
-            // TODO choose candidate at random! Disabled to allow proper performance comparisons
-            .find_map(|candidates| candidates.last().cloned())
+        None
     }
 
     #[inline]
-    pub fn assign_lit(&mut self, lit: Literal) {
-        self.unassigned_lits.remove(&lit);
-        self.unassigned_lits.remove(&lit.complementary());
+    fn assign_lit(&mut self, lit: Literal) {
+        let var = lit.var() as usize;
+        self.add_var(lit.var());
+
+        self.assigned[var] = true;
+        self.assigned_at[var] = self.conflicts;
+        self.participations[var] = 0;
     }
 
     #[inline]
-    pub fn un_assign_lit(&mut self, lit: Literal) {
-        self.unassigned_lits.insert(lit);
-        self.unassigned_lits.insert(lit.complementary());
+    fn un_assign_lit(&mut self, lit: Literal) {
+        let var = lit.var() as usize;
+        self.assigned[var] = false;
+
+        let interval = self.conflicts - self.assigned_at[var];
+        if interval > 0 {
+            let learning_rate = self.participations[var] as f64 / interval as f64;
+            self.scores[var] += self.step_size * (learning_rate - self.scores[var]);
+        }
+        self.decayed_at[var] = self.conflicts;
+
+        self.heap.push(lit.var(), &self.scores);
+    }
+
+    fn on_conflict(&mut self, vars: &[u32]) {
+        self.conflicts += 1;
+        vars.iter().for_each(|&var| self.participations[var as usize] += 1);
+
+        self.step_size = (self.step_size - STEP_SIZE_DECREMENT).max(MIN_STEP_SIZE);
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.randomness.seed(seed);
+
+        for var in 1..self.scores.len() as u32 {
+            self.heap.set_tie_breaker(var, self.randomness.tie_breaker(var), &self.scores);
+        }
+    }
+
+    fn set_random_decision_fraction(&mut self, fraction: f64) {
+        self.randomness.decision_fraction = fraction;
+    }
+}
+
+/// Seeded source of the random choices of a decider.
+struct Randomness {
+    random: Random,
+    seeded: bool,
+    decision_fraction: f64,
+}
+
+impl Randomness {
+    fn new() -> Self {
+        Randomness {
+            random: Random::new(0),
+            seeded: false,
+            decision_fraction: 0.0,
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.random = Random::new(seed);
+        self.seeded = true;
     }
 
-    fn age_factor(&self) -> u64 {
-        self.age >> 3 // self.age / 8
+    /// Key ordering `var` among variables of equal scores, the highest first: random once seeded,
+    /// else decreasing with the variable.
+    fn tie_breaker(&mut self, var: u32) -> u64 {
+        if self.seeded {
+            self.random.next_u64()
+        } else {
+            u64::MAX - u64::from(var)
+        }
     }
 
-    fn contains_unassigned_literal(&self, lits: &LiteralSet) -> bool {
-        !lits.is_disjoint(&self.unassigned_lits)
+    /// For `decision_fraction` of the calls, picks a variable at random and returns it if it is
+    /// unassigned, the way MiniSat does.
+    fn random_var(&mut self, assigned: &[bool]) -> Option<u32> {
+        if self.decision_fraction <= 0.0 || assigned.len() < 2 || self.random.next_f64() >= self.decision_fraction {
+            return None;
+        }
+
+        let var = 1 + self.random.next_below(assigned.len() as u64 - 1) as usize;
+        if assigned[var] {
+            None
+        } else {
+            Some(var as u32)
+        }
+    }
+}
+
+/// Binary max-heap of variables, ordered by the scores it is given, then by their tie breakers.
+/// Scores of the variables in the heap must only change through `update`.
+struct VarHeap {
+    vars: Vec<u32>,
+    // Position of every variable in vars, if it is in the heap
+    positions: Vec<Option<usize>>,
+    tie_breakers: Vec<u64>,
+}
+
+impl VarHeap {
+    fn new() -> Self {
+        VarHeap {
+            vars: Vec::new(),
+            positions: Vec::new(),
+            tie_breakers: Vec::new(),
+        }
+    }
+
+    fn set_tie_breaker(&mut self, var: u32, tie_breaker: u64, scores: &[f64]) {
+        if var as usize >= self.tie_breakers.len() {
+            self.tie_breakers.resize(var as usize + 1, 0);
+        }
+
+        self.tie_breakers[var as usize] = tie_breaker;
+        self.update(var, scores);
+    }
+
+    fn position(&self, var: u32) -> Option<usize> {
+        self.positions.get(var as usize).cloned().unwrap_or(None)
+    }
+
+    fn push(&mut self, var: u32, scores: &[f64]) {
+        if self.position(var).is_some() {
+            return;
+        }
+
+        if var as usize >= self.positions.len() {
+            self.positions.resize(var as usize + 1, None);
+        }
+        if var as usize >= self.tie_breakers.len() {
+            self.tie_breakers.resize(var as usize + 1, 0);
+        }
+
+        self.vars.push(var);
+        self.sift_up(self.vars.len() - 1, scores);
+    }
+
+    fn peek(&self) -> Option<u32> {
+        self.vars.first().cloned()
+    }
+
+    fn pop(&mut self, scores: &[f64]) -> Option<u32> {
+        if self.vars.is_empty() {
+            return None;
+        }
+
+        let top = self.vars.swap_remove(0);
+        self.positions[top as usize] = None;
+
+        if !self.vars.is_empty() {
+            self.sift_down(0, scores);
+        }
+
+        Some(top)
+    }
+
+    /// Pops variables until an unassigned one is found. Assigned variables get pushed back when
+    /// they are unassigned.
+    fn pop_unassigned(&mut self, scores: &[f64], assigned: &[bool]) -> Option<u32> {
+        while let Some(var) = self.pop(scores) {
+            if !assigned[var as usize] {
+                return Some(var);
+            }
+        }
+
+        None
+    }
+
+    /// Moves `var` to its place after its score changed.
+    fn update(&mut self, var: u32, scores: &[f64]) {
+        if let Some(position) = self.position(var) {
+            self.sift_up(position, scores);
+            self.sift_down(self.position(var).unwrap(), scores);
+        }
+    }
+
+    fn sift_up(&mut self, mut position: usize, scores: &[f64]) {
+        let var = self.vars[position];
+
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if !self.precedes(var, self.vars[parent], scores) {
+                break;
+            }
+
+            self.place(self.vars[parent], position);
+            position = parent;
+        }
+
+        self.place(var, position);
+    }
+
+    fn sift_down(&mut self, mut position: usize, scores: &[f64]) {
+        let var = self.vars[position];
+
+        loop {
+            let left = 2 * position + 1;
+            if left >= self.vars.len() {
+                break;
+            }
+
+            let right = left + 1;
+            let child = if right < self.vars.len() && self.precedes(self.vars[right], self.vars[left], scores) {
+                right
+            } else {
+                left
+            };
+
+            if !self.precedes(self.vars[child], var, scores) {
+                break;
+            }
+
+            self.place(self.vars[child], position);
+            position = child;
+        }
+
+        self.place(var, position);
+    }
+
+    #[inline]
+    fn precedes(&self, var: u32, other_var: u32, scores: &[f64]) -> bool {
+        let (score, other_score) = (scores[var as usize], scores[other_var as usize]);
+
+        score > other_score || (score == other_score && self.tie_breakers[var as usize] > self.tie_breakers[other_var as usize])
+    }
+
+    #[inline]
+    fn place(&mut self, var: u32, position: usize) {
+        self.vars[position] = var;
+        self.positions[var as usize] = Some(position);
     }
 }
@@ -0,0 +1,230 @@
+use model::Literal;
+use model::LiteralVec;
+use model::Trail;
+use model::Xor;
+use std::iter;
+
+/**
+ * Gauss-Jordan elimination on XOR constraints, kept as the rows of a matrix over GF(2), one column
+ * for each of their variables. Every row has a basic column, which occurs in no other row and is
+ * left unassigned whenever possible: when its variable gets assigned, another unassigned variable
+ * of the row becomes basic and is eliminated from the other rows. A row then implies its last
+ * unassigned variable, and is a conflict once all of its variables are assigned with the wrong
+ * parity. Since rows stay combinations of the constraints, nothing is undone when backtracking.
+ *
+ * see https://doi.org/10.1007/978-3-642-02777-2_24
+ */
+pub(crate) struct GaussMatrix {
+    // Variable of every column
+    vars: Vec<u32>,
+    // Column of every variable, indexed by variable
+    columns: Vec<Option<usize>>,
+    rows: Vec<Row>,
+}
+
+struct Row {
+    bits: Vec<u64>,
+    parity: bool,
+    basic_column: usize,
+}
+
+impl GaussMatrix {
+    /// Matrix of the `xors` constraints in reduced row echelon form, `None` if they contradict each
+    /// other.
+    pub fn new(xors: &[Xor]) -> Option<Self> {
+        let mut vars: Vec<u32> = xors.iter().flat_map(|xor| xor.vars().to_vec()).collect();
+        vars.sort_unstable();
+        vars.dedup();
+
+        let mut columns = vec![None; vars.last().map_or(0, |&var| var as usize + 1)];
+        vars.iter().enumerate().for_each(|(column, &var)| columns[var as usize] = Some(column));
+
+        let num_words = vars.len().div_ceil(64);
+        let mut rows: Vec<Row> = xors.iter()
+            .map(|xor| {
+                let mut bits = vec![0; num_words];
+                xor.vars().iter().filter_map(|&var| columns[var as usize]).for_each(|column| set_bit(&mut bits, column));
+
+                Row { bits, parity: xor.parity(), basic_column: 0 }
+            })
+            .collect();
+
+        let mut num_basic_rows = 0;
+        for column in 0..vars.len() {
+            let pivot = match (num_basic_rows..rows.len()).find(|&row| has_bit(&rows[row].bits, column)) {
+                Some(pivot) => pivot,
+                None => continue,
+            };
+
+            rows.swap(num_basic_rows, pivot);
+            rows[num_basic_rows].basic_column = column;
+            eliminate(&mut rows, num_basic_rows, column);
+            num_basic_rows += 1;
+        }
+
+        // The rows left are empty, the constraints they came from were combinations of the others
+        if rows[num_basic_rows..].iter().any(|row| row.parity) {
+            return None;
+        }
+        rows.truncate(num_basic_rows);
+
+        Some(GaussMatrix { vars, columns, rows })
+    }
+
+    pub fn contains(&self, var: u32) -> bool {
+        self.columns.get(var as usize).is_some_and(|column| column.is_some())
+    }
+
+    /// Moves the basic columns away from the variables assigned on `trail`, then returns the
+    /// clauses explaining the variables implied by the rows, their implied literal first, or the
+    /// clause falsified by a conflicting row.
+    pub fn propagate(&mut self, trail: &Trail) -> Result<Vec<LiteralVec>, LiteralVec> {
+        let values: Vec<Option<bool>> = self.vars.iter()
+            .map(|&var| trail.value(Literal::from_var(var, true)))
+            .collect();
+
+        let num_words = self.vars.len().div_ceil(64);
+        let mut unassigned_bits = vec![0; num_words];
+        let mut true_bits = vec![0; num_words];
+        for (column, value) in values.iter().enumerate() {
+            match value {
+                None => set_bit(&mut unassigned_bits, column),
+                Some(true) => set_bit(&mut true_bits, column),
+                Some(false) => {},
+            }
+        }
+
+        for row in 0..self.rows.len() {
+            if values[self.rows[row].basic_column].is_none() {
+                continue;
+            }
+
+            // The other basic columns do not occur in the row, so any unassigned one can be basic
+            if let Some(column) = first_common_bit(&self.rows[row].bits, &unassigned_bits) {
+                self.rows[row].basic_column = column;
+                eliminate(&mut self.rows, row, column);
+            }
+        }
+
+        let mut explanations = Vec::new();
+
+        for row in &self.rows {
+            let mut unassigned_columns = common_bits(&row.bits, &unassigned_bits);
+            let implied_column = unassigned_columns.next();
+            if unassigned_columns.next().is_some() {
+                continue;
+            }
+
+            let true_vars: u32 = row.bits.iter().zip(&true_bits).map(|(bits, true_bits)| (bits & true_bits).count_ones()).sum();
+            let missing_parity = row.parity != (true_vars % 2 == 1);
+
+            let assigned_lits = common_bits(&row.bits, &row.bits)
+                .filter(|&column| Some(column) != implied_column)
+                .map(|column| Literal::from_var(self.vars[column], !values[column].unwrap()));
+
+            match implied_column {
+                None if missing_parity => return Err(assigned_lits.collect()),
+                None => {},
+                Some(column) => {
+                    let implied_lit = Literal::from_var(self.vars[column], missing_parity);
+                    explanations.push(Some(implied_lit).into_iter().chain(assigned_lits).collect());
+                },
+            }
+        }
+
+        Ok(explanations)
+    }
+}
+
+/// Adds the row at `position` to the other rows holding `column`, which then only occurs in it.
+fn eliminate(rows: &mut [Row], position: usize, column: usize) {
+    let bits = rows[position].bits.clone();
+    let parity = rows[position].parity;
+
+    for (other_position, row) in rows.iter_mut().enumerate() {
+        if other_position != position && has_bit(&row.bits, column) {
+            row.bits.iter_mut().zip(&bits).for_each(|(word, other_word)| *word ^= other_word);
+            row.parity ^= parity;
+        }
+    }
+}
+
+#[inline]
+fn has_bit(bits: &[u64], column: usize) -> bool {
+    bits[column / 64] >> (column % 64) & 1 == 1
+}
+
+#[inline]
+fn set_bit(bits: &mut [u64], column: usize) {
+    bits[column / 64] |= 1 << (column % 64);
+}
+
+fn first_common_bit(bits: &[u64], other_bits: &[u64]) -> Option<usize> {
+    common_bits(bits, other_bits).next()
+}
+
+/// Columns set in both `bits` and `other_bits`, in increasing order.
+fn common_bits<'a>(bits: &'a [u64], other_bits: &'a [u64]) -> impl Iterator<Item=usize> + 'a {
+    bits.iter().zip(other_bits).enumerate().flat_map(|(position, (word, other_word))| {
+        let mut common_word = word & other_word;
+
+        iter::from_fn(move || {
+            if common_word == 0 {
+                return None;
+            }
+
+            let bit = common_word.trailing_zeros() as usize;
+            common_word &= common_word - 1;
+            Some(position * 64 + bit)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lits(dimacs_lits: &[i32]) -> LiteralVec {
+        dimacs_lits.iter().map(|&lit| Literal::from(lit)).collect()
+    }
+
+    fn trail(dimacs_lits: &[i32]) -> Trail {
+        let mut trail = Trail::new();
+        trail.new_decision_level();
+        dimacs_lits.iter().for_each(|&lit| trail.assign(Literal::from(lit), 1, None));
+
+        trail
+    }
+
+    #[test]
+    fn contradictory_xors_have_no_matrix() {
+        let xors = [Xor::new(&[1, 2], true), Xor::new(&[2, 3], true), Xor::new(&[1, 3], true)];
+        assert!(GaussMatrix::new(&xors).is_none());
+
+        let xors = [Xor::new(&[1, 2], true), Xor::new(&[2, 3], true), Xor::new(&[1, 3], false)];
+        assert!(GaussMatrix::new(&xors).is_some());
+    }
+
+    #[test]
+    fn explanations_hold_their_implied_literal_first() {
+        let mut matrix = GaussMatrix::new(&[Xor::new(&[1, 2, 3], true), Xor::new(&[3, 4], false)]).unwrap();
+        let trail = trail(&[1, -2]);
+
+        let mut explanations = matrix.propagate(&trail).unwrap();
+        explanations.sort();
+
+        // x1 true and x2 false leave x3 and x4 false
+        assert_eq!(explanations, vec![lits(&[-4, -1, 2]), lits(&[-3, -1, 2])]);
+        for explanation in &explanations {
+            assert_eq!(trail.value(explanation[0]), None);
+            assert!(explanation[1..].iter().all(|&lit| trail.is_false(lit)));
+        }
+    }
+
+    #[test]
+    fn conflicting_row_gives_its_falsified_clause() {
+        let mut matrix = GaussMatrix::new(&[Xor::new(&[1, 2], true), Xor::new(&[2, 3], false)]).unwrap();
+
+        assert_eq!(matrix.propagate(&trail(&[1, 3])), Err(lits(&[-1, -3])));
+    }
+}
@@ -0,0 +1,25 @@
+//! cnsat is a CDCL SAT solver.
+//!
+//! The entry point is [`Solver`](solver/struct.Solver.html): create variables, add clauses from
+//! plain DIMACS literals and call `solve`. CNF files can be loaded with
+//! [`parse_dimacs_file`](parser/fn.parse_dimacs_file.html).
+
+extern crate fnv;
+extern crate dimacs;
+
+pub mod checker;
+pub mod decider;
+pub mod model;
+pub mod parser;
+pub mod polarity;
+pub mod proof;
+pub mod restart;
+pub mod solver;
+pub mod verifier;
+mod conflict_analyzer;
+mod gauss;
+mod preprocessing;
+mod random;
+
+pub use parser::parse_dimacs_file;
+pub use solver::Solver;
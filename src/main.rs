@@ -1,42 +1,350 @@
-extern crate fnv;
-extern crate dimacs;
+extern crate cnsat;
 
-use parser::parse_dimacs_file;
+use cnsat::checker::check_proof_file;
+use cnsat::checker::CertificateFormat;
+use cnsat::decider::decider_from_name;
+use cnsat::decider::Decider;
+use cnsat::parse_dimacs_file;
+use cnsat::parser::parse_dimacs_xors;
+use cnsat::polarity::polarity_from_name;
+use cnsat::polarity::Polarity;
+use cnsat::proof::Proof;
+use cnsat::proof::ProofFormat;
+use cnsat::restart::restart_strategy_from_name;
+use cnsat::restart::RestartStrategy;
+use cnsat::verifier::verify_model;
+use cnsat::verifier::verify_xors;
+use cnsat::model::Literal;
+use cnsat::model::LiteralSet;
+use cnsat::solver::Statistics;
+use cnsat::Solver;
 use std::env;
-//use model::clause_vec_to_string;
-use solver::Solver;
+use std::process;
+use std::time::Duration;
+use std::time::Instant;
 
-mod solver;
-mod model;
-mod decider;
-mod conflict_analyzer;
-mod parser;
+const USAGE: &str = "usage: cnsat [--proof <file>] [--binary-proof] [--verify]
+             [--restarts <none|fixed|geometric|luby|glucose>] [--chrono <levels>]
+             [--polarity <saved|false|true|random|target|best>]
+             [--decider <vsids|vmtf|chb|lrb>] [--seed <n>]
+             [--random-decisions <fraction>] [--no-elimination]
+             [--no-subsumption] [--no-probing] [--no-vivification]
+             [--vivify-original] [--no-gauss] <file.cnf>
+       cnsat check [--lrat] <file.cnf> <proof>
+             (LRAT proofs with RAT hints, i.e. negative clause ids, are not
+             supported)";
+
+// Exit codes expected by SAT competition harnesses
+const EXIT_UNKNOWN: i32 = 0;
+const EXIT_SAT: i32 = 10;
+const EXIT_UNSAT: i32 = 20;
+
+const MAX_LINE_LENGTH: usize = 80;
+
+enum Command {
+    Solve(Options),
+    Check(CheckOptions),
+}
+
+struct Options {
+    filename: String,
+    proof_filename: Option<String>,
+    proof_format: ProofFormat,
+    verify_model: bool,
+    restart_strategy: Option<Box<dyn RestartStrategy>>,
+    chrono_threshold: Option<u32>,
+    polarity: Option<Polarity>,
+    decider: Option<Box<dyn Decider>>,
+    seed: Option<u64>,
+    random_decision_fraction: f64,
+    elimination: bool,
+    subsumption: bool,
+    probing: bool,
+    vivification: bool,
+    original_vivification: bool,
+    gaussian_elimination: bool,
+}
+
+struct CheckOptions {
+    cnf_filename: String,
+    proof_filename: String,
+    format: CertificateFormat,
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    if args.get(1).map(|arg| arg.as_str()) == Some("check") {
+        return parse_check_args(&args[2..]).map(Command::Check);
+    }
+
+    parse_solve_args(&args[1..]).map(Command::Solve)
+}
+
+fn parse_solve_args(args: &[String]) -> Result<Options, String> {
+    let mut filename = None;
+    let mut proof_filename = None;
+    let mut proof_format = ProofFormat::Text;
+    // Models are always verified in debug builds
+    let mut verify_model = cfg!(debug_assertions);
+    let mut restart_strategy = None;
+    let mut chrono_threshold = None;
+    let mut polarity = None;
+    let mut decider = None;
+    let mut seed = None;
+    let mut random_decision_fraction = 0.0;
+    let mut elimination = true;
+    let mut subsumption = true;
+    let mut probing = true;
+    let mut vivification = true;
+    let mut original_vivification = false;
+    let mut gaussian_elimination = true;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--proof" => {
+                let value = args.next().ok_or_else(|| String::from("--proof expects a filename"))?;
+                proof_filename = Some(value.clone());
+            },
+            "--binary-proof" => proof_format = ProofFormat::Binary,
+            "--verify" => verify_model = true,
+            "--restarts" => {
+                let value = args.next().ok_or_else(|| String::from("--restarts expects a strategy"))?;
+                restart_strategy = Some(restart_strategy_from_name(value)?);
+            },
+            "--chrono" => {
+                let value = args.next().and_then(|value| value.parse().ok())
+                    .ok_or_else(|| String::from("--chrono expects a number of levels"))?;
+                chrono_threshold = Some(value);
+            },
+            "--polarity" => {
+                let value = args.next().ok_or_else(|| String::from("--polarity expects a polarity"))?;
+                polarity = Some(polarity_from_name(value)?);
+            },
+            "--decider" => {
+                let value = args.next().ok_or_else(|| String::from("--decider expects a heuristic"))?;
+                decider = Some(decider_from_name(value)?);
+            },
+            "--seed" => {
+                let value = args.next().and_then(|value| value.parse().ok())
+                    .ok_or_else(|| String::from("--seed expects a number"))?;
+                seed = Some(value);
+            },
+            "--random-decisions" => {
+                random_decision_fraction = args.next().and_then(|value| value.parse().ok())
+                    .filter(|fraction| (0.0..=1.0).contains(fraction))
+                    .ok_or_else(|| String::from("--random-decisions expects a fraction between 0 and 1"))?;
+            },
+            "--no-elimination" => elimination = false,
+            "--no-subsumption" => subsumption = false,
+            "--no-probing" => probing = false,
+            "--no-vivification" => vivification = false,
+            "--vivify-original" => original_vivification = true,
+            "--no-gauss" => gaussian_elimination = false,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option: {}", arg)),
+            _ => filename = Some(arg.clone()),
+        }
+    }
+
+    Ok(Options {
+        filename: filename.ok_or_else(|| String::from("must provide a .cnf filename!"))?,
+        proof_filename,
+        proof_format,
+        verify_model,
+        restart_strategy,
+        chrono_threshold,
+        polarity,
+        decider,
+        seed,
+        random_decision_fraction,
+        elimination,
+        subsumption,
+        probing,
+        vivification,
+        original_vivification,
+        gaussian_elimination,
+    })
+}
+
+fn parse_check_args(args: &[String]) -> Result<CheckOptions, String> {
+    let mut filenames = Vec::new();
+    let mut format = CertificateFormat::Drat;
+
+    for arg in args {
+        match arg.as_str() {
+            "--lrat" => format = CertificateFormat::Lrat,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option: {}", arg)),
+            _ => filenames.push(arg.clone()),
+        }
+    }
+
+    if filenames.len() != 2 {
+        return Err(String::from("check expects a .cnf filename and a proof filename"));
+    }
+
+    Ok(CheckOptions {
+        proof_filename: filenames.pop().unwrap(),
+        cnf_filename: filenames.pop().unwrap(),
+        format,
+    })
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() < 2 {
-        println!("must provide a .cnf filename!");
-        return;
+    match parse_args(&args) {
+        Ok(Command::Solve(options)) => process::exit(solve(options)),
+        Ok(Command::Check(options)) => check(options),
+        Err(err) => {
+            // Kept off stdout, which harnesses parse for the solver's answer
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(1);
+        },
+    }
+}
+
+fn check(options: CheckOptions) {
+    match check_proof_file(&options.cnf_filename, &options.proof_filename, options.format) {
+        Ok(()) => println!("VERIFIED"),
+        Err(err) => {
+            println!("NOT VERIFIED: {}", err);
+            process::exit(1);
+        },
     }
+}
 
-    let parse_result = parse_dimacs_file(&args[1]);
+fn solve(options: Options) -> i32 {
+    let start = Instant::now();
+    let parse_result = parse_dimacs_file(&options.filename)
+        .and_then(|clauses| parse_dimacs_xors(&options.filename).map(|xors| (clauses, xors)));
 
     if parse_result.is_err() {
-        println!("Error: {}", parse_result.err().unwrap());
-        return;
+        println!("c Error: {}", parse_result.err().unwrap());
+        println!("s UNKNOWN");
+        return EXIT_UNKNOWN;
     }
 
     let mut solver = Solver::new();
+    solver.set_chrono_threshold(options.chrono_threshold);
+    solver.set_elimination(options.elimination);
+    solver.set_subsumption(options.subsumption);
+    solver.set_probing(options.probing);
+    solver.set_vivification(options.vivification);
+    solver.set_original_vivification(options.original_vivification);
+    solver.set_gaussian_elimination(options.gaussian_elimination);
+
+    if let Some(polarity) = options.polarity {
+        solver.set_polarity(polarity);
+    }
+
+    solver.set_random_decision_fraction(options.random_decision_fraction);
+
+    if let Some(seed) = options.seed {
+        solver.set_seed(seed);
+    }
+
+    if let Some(decider) = options.decider {
+        solver.set_decider(decider);
+    }
+
+    if let Some(restart_strategy) = options.restart_strategy {
+        solver.set_restart_strategy(restart_strategy);
+    }
 
-    parse_result.unwrap().into_iter().for_each(|clause| {
+    if let Some(proof_filename) = options.proof_filename {
+        match Proof::create(&proof_filename, options.proof_format) {
+            Ok(proof) => solver.set_proof(proof),
+            Err(err) => {
+                println!("c Error: {}", err);
+                println!("s UNKNOWN");
+                return EXIT_UNKNOWN;
+            },
+        }
+    }
+
+    let (clauses, xors) = parse_result.unwrap();
+    // Variables past these are the fresh ones of XOR constraints turned into clauses
+    let num_formula_vars = clauses.iter().flat_map(|clause| clause.lits().iter().map(|lit| lit.var()))
+        .chain(xors.iter().flat_map(|xor| xor.vars().iter().cloned()))
+        .max()
+        .unwrap_or(0);
+    clauses.into_iter().for_each(|clause| {
         solver.add_clause(clause);
     });
+    xors.into_iter().for_each(|xor| {
+        solver.add_xor(xor);
+    });
 
     let solution = solver.solve();
 
+    if let Some(ref model) = solution {
+        if options.verify_model {
+            let filename = &options.filename;
+            let verification = parse_dimacs_file(filename)
+                .and_then(|clauses| verify_model(&clauses, model))
+                .and_then(|_| parse_dimacs_xors(filename))
+                .and_then(|xors| verify_xors(&xors, model));
+
+            if let Err(err) = verification {
+                eprintln!("Error: model verification failed: {}", err);
+                process::exit(1);
+            }
+        }
+    }
+
+    if let Some(Err(err)) = solver.take_proof().map(|proof| proof.finish()) {
+        println!("c Error: {}", err);
+    }
+
+    print_statistics(solver.statistics(), start.elapsed());
+
     match solution {
-        Some(model) => println!("SAT\nmodel: {:?}", model),
-        None => println!("UNSAT"),
+        Some(model) => {
+            println!("s SATISFIABLE");
+            print_model(&model, num_formula_vars);
+            EXIT_SAT
+        },
+        None => {
+            println!("s UNSATISFIABLE");
+            EXIT_UNSAT
+        },
     }
 }
+
+fn print_statistics(statistics: &Statistics, elapsed: Duration) {
+    println!("c decisions: {}", statistics.decisions);
+    println!("c propagations: {}", statistics.propagations);
+    println!("c conflicts: {}", statistics.conflicts);
+    println!("c learnt clauses: {}", statistics.learnt_clauses);
+    println!("c deleted clauses: {}", statistics.deleted_clauses);
+    println!("c restarts: {}", statistics.restarts);
+    println!("c chronological backtracks: {}", statistics.chrono_backtracks);
+    println!("c eliminated variables: {}", statistics.eliminated_vars);
+    println!("c subsumed clauses: {}", statistics.subsumed_clauses);
+    println!("c strengthened clauses: {}", statistics.strengthened_clauses);
+    println!("c failed literals: {}", statistics.failed_literals);
+    println!("c implied literals: {}", statistics.implied_literals);
+    println!("c substituted variables: {}", statistics.substituted_vars);
+    println!("c vivified clauses: {}", statistics.vivified_clauses);
+    println!("c detected XOR constraints: {}", statistics.detected_xors);
+    println!("c solve time: {}.{:03}s", elapsed.as_secs(), elapsed.subsec_millis());
+}
+
+/// Prints the model of the first `num_vars` variables as `v` lines sorted by variable and
+/// terminated by 0.
+fn print_model(model: &LiteralSet, num_vars: u32) {
+    let mut lits: Vec<Literal> = model.iter().cloned().filter(|lit| lit.var() <= num_vars).collect();
+    lits.sort_by_key(|lit| lit.var());
+
+    let mut line = String::from("v");
+    for lit in lits {
+        let formatted_lit = format!(" {}", lit);
+
+        if line.len() + formatted_lit.len() > MAX_LINE_LENGTH {
+            println!("{}", line);
+            line = String::from("v");
+        }
+        line.push_str(&formatted_lit);
+    }
+
+    println!("{} 0", line);
+}
@@ -1,15 +1,15 @@
 use model::Literal;
 use model::LiteralVec;
 use std::fmt;
-use model::LiteralSet;
+use std::hash::Hash;
+use std::hash::Hasher;
 
-pub type ClauseId = usize;
-
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+/// A clause of the formula, or learnt from it. The solver copies the clauses it is given into its
+/// clause database.
+#[derive(Debug, Clone)]
 pub struct Clause {
     lits: LiteralVec,
-    lit_a: Literal,
-    lit_b: Option<Literal>,
+    lbd: u32,
 }
 
 impl Clause {
@@ -17,21 +17,31 @@ impl Clause {
         let clause_lits: LiteralVec = clause.lits().iter()
             .map(|&lit| Literal::from_dimacs_lit(lit))
             .collect();
-        let lit_a = clause_lits.get(0).cloned().expect("A clause cannot be empty");
-        let lit_b = clause_lits.get(1).cloned();
+
+        Clause::from_lits(&clause_lits)
+    }
+
+    /// Builds a clause out of `lits`, dropping repeated literals.
+    pub fn from_lits(lits: &[Literal]) -> Self {
+        let mut clause_lits = LiteralVec::with_capacity(lits.len());
+        lits.iter().cloned().for_each(|lit| {
+            if !clause_lits.contains(&lit) {
+                clause_lits.push(lit);
+            }
+        });
 
         Clause {
             lits: clause_lits,
-            lit_a,
-            lit_b,
+            lbd: 0,
         }
     }
 
-    pub fn new_asserting_clause(last_lit: Literal, strengthen_lits: LiteralVec) -> Self {
+    /// Clause learnt from a conflict. `lits` starts with its asserting literal, followed by the one
+    /// falsified at the highest level.
+    pub(crate) fn new_asserting_clause(lits: LiteralVec, lbd: u32) -> Self {
         Clause {
-            lits: strengthen_lits,
-            lit_a: last_lit,
-            lit_b: None,
+            lits,
+            lbd,
         }
     }
 
@@ -39,46 +49,35 @@ impl Clause {
         &self.lits
     }
 
-    #[inline]
-    pub fn first_watched_lit(&self) -> Literal {
-        self.lit_a
+    pub fn is_empty(&self) -> bool {
+        self.lits.is_empty()
     }
 
-    pub fn second_watched_lit(&self) -> Option<Literal> {
-        self.lit_b
+    pub fn is_tautology(&self) -> bool {
+        self.lits.iter().any(|lit| self.lits.contains(&lit.complementary()))
     }
 
-    pub fn is_unary(&self) -> bool {
-        self.lit_b.is_none()
+    /// Literal block distance of the clause when it was learnt, 0 for original clauses.
+    pub fn lbd(&self) -> u32 {
+        self.lbd
     }
 
-    pub fn strengthen(&mut self, lit: Literal, assigned_lits: &LiteralSet) -> Option<Literal> {
-        if self.lit_a == lit {
-            self.lit_a = self.lit_b.expect("Cannot strengthen unary clauses");
-        } else if self.lit_b.is_none() || self.lit_b.unwrap() != lit {
-            panic!("strengthen must be called based on 2-watched literals");
-        }
-
-        self.lit_b = self.find_new_second(assigned_lits);
-
-        self.lit_b
+    pub(crate) fn lits_mut(&mut self) -> &mut [Literal] {
+        &mut self.lits
     }
+}
 
-    pub fn un_strengthen(&mut self, assigned_lits: &LiteralSet) -> Option<Literal> {
-        self.lit_b = self.find_new_second(assigned_lits);
-
-        self.lit_b
+impl PartialEq for Clause {
+    fn eq(&self, other: &Clause) -> bool {
+        self.lits == other.lits
     }
+}
 
-    pub fn can_be_strengthen_by(&self, lit: Literal) -> bool {
-        self.lits.contains(&lit.complementary())
-    }
+impl Eq for Clause {}
 
-    #[inline]
-    fn find_new_second(&self, assigned_lits: &LiteralSet) -> Option<Literal> {
-        self.lits.iter().cloned()
-            .filter(|&lit| lit != self.lit_a)
-            .find(|lit| !assigned_lits.contains(&lit.complementary()))
+impl Hash for Clause {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.lits.hash(state);
     }
 }
 
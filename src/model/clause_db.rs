@@ -0,0 +1,213 @@
+use model::Clause;
+use model::Literal;
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter;
+use std::slice;
+
+// Every clause starts with a header of three words: its length, its LBD along with its flags, and
+// its activity
+const HEADER_SIZE: usize = 3;
+const LEARNT: u32 = 1;
+const DELETED: u32 = 1 << 1;
+const VIVIFIED: u32 = 1 << 2;
+const XOR_REASON: u32 = 1 << 3;
+const FLAG_BITS: u32 = 4;
+
+/// Reference to a clause of a `ClauseDb`: the position of its header in the arena. It is valid
+/// until the next garbage collection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct ClauseRef(u32);
+
+/// Clause database of the solver, kept in a single arena of words where every clause is a header
+/// followed by its literals. Deleted clauses keep their space until the garbage is collected, which
+/// moves the remaining clauses.
+#[derive(Debug, Default)]
+pub(crate) struct ClauseDb {
+    arena: Vec<u32>,
+    // Words taken by deleted clauses
+    garbage: usize,
+}
+
+impl ClauseDb {
+    pub fn new() -> Self {
+        ClauseDb::default()
+    }
+
+    /// Copies `clause` into the arena, along with its LBD.
+    pub fn add(&mut self, clause: &Clause, learnt: bool) -> ClauseRef {
+        let flags = if learnt { LEARNT } else { 0 };
+
+        self.push(clause.lits(), clause.lbd() << FLAG_BITS | flags)
+    }
+
+    /// Copies into the arena a clause explaining a deduction of the XOR constraints. It is neither
+    /// original nor learnt, and is left out of the clauses `iter` gives.
+    pub fn add_xor_reason(&mut self, lits: &[Literal]) -> ClauseRef {
+        self.push(lits, XOR_REASON)
+    }
+
+    fn push(&mut self, lits: &[Literal], lbd_and_flags: u32) -> ClauseRef {
+        let clause_ref = u32::try_from(self.arena.len())
+            .map(ClauseRef)
+            .expect("The clause database cannot hold more than 2^32 words");
+
+        self.arena.push(lits.len() as u32);
+        self.arena.push(lbd_and_flags);
+        self.arena.push(0.0f32.to_bits());
+        self.arena.extend(lits.iter().map(|lit| lit.to_i32() as u32));
+
+        clause_ref
+    }
+
+    #[inline]
+    pub fn len(&self, clause_ref: ClauseRef) -> usize {
+        self.arena[clause_ref.0 as usize] as usize
+    }
+
+    #[inline]
+    pub fn lits(&self, clause_ref: ClauseRef) -> &[Literal] {
+        let start = clause_ref.0 as usize + HEADER_SIZE;
+        let words = &self.arena[start..start + self.len(clause_ref)];
+
+        // SAFETY: a literal is a transparent i32, stored as its bits
+        unsafe { slice::from_raw_parts(words.as_ptr() as *const Literal, words.len()) }
+    }
+
+    #[inline]
+    pub fn lits_mut(&mut self, clause_ref: ClauseRef) -> &mut [Literal] {
+        let start = clause_ref.0 as usize + HEADER_SIZE;
+        let end = start + self.len(clause_ref);
+        let words = &mut self.arena[start..end];
+
+        // SAFETY: a literal is a transparent i32, stored as its bits
+        unsafe { slice::from_raw_parts_mut(words.as_mut_ptr() as *mut Literal, words.len()) }
+    }
+
+    /// Literal block distance of the clause when it was learnt, 0 for original clauses.
+    pub fn lbd(&self, clause_ref: ClauseRef) -> u32 {
+        self.arena[clause_ref.0 as usize + 1] >> FLAG_BITS
+    }
+
+    pub fn is_learnt(&self, clause_ref: ClauseRef) -> bool {
+        self.arena[clause_ref.0 as usize + 1] & LEARNT != 0
+    }
+
+    pub fn is_deleted(&self, clause_ref: ClauseRef) -> bool {
+        self.arena[clause_ref.0 as usize + 1] & DELETED != 0
+    }
+
+    pub fn is_xor_reason(&self, clause_ref: ClauseRef) -> bool {
+        self.arena[clause_ref.0 as usize + 1] & XOR_REASON != 0
+    }
+
+    pub fn is_vivified(&self, clause_ref: ClauseRef) -> bool {
+        self.arena[clause_ref.0 as usize + 1] & VIVIFIED != 0
+    }
+
+    /// Marks the clause as vivified, so that it is not vivified again.
+    pub fn mark_vivified(&mut self, clause_ref: ClauseRef) {
+        self.arena[clause_ref.0 as usize + 1] |= VIVIFIED;
+    }
+
+    pub fn activity(&self, clause_ref: ClauseRef) -> f64 {
+        f64::from(f32::from_bits(self.arena[clause_ref.0 as usize + 2]))
+    }
+
+    pub fn bump_activity(&mut self, clause_ref: ClauseRef, increment: f64) {
+        self.set_activity(clause_ref, self.activity(clause_ref) + increment);
+    }
+
+    pub fn scale_activity(&mut self, clause_ref: ClauseRef, factor: f64) {
+        self.set_activity(clause_ref, self.activity(clause_ref) * factor);
+    }
+
+    fn set_activity(&mut self, clause_ref: ClauseRef, activity: f64) {
+        self.arena[clause_ref.0 as usize + 2] = (activity as f32).to_bits();
+    }
+
+    /// Marks the clause as deleted. Its space is reclaimed by the next garbage collection.
+    pub fn delete(&mut self, clause_ref: ClauseRef) {
+        self.arena[clause_ref.0 as usize + 1] |= DELETED;
+        self.garbage += HEADER_SIZE + self.len(clause_ref);
+    }
+
+    /// References of the clauses not deleted, in the order they were added. Explanations of the XOR
+    /// constraints are left out, as they are not part of the formula.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item=ClauseRef> + 'a {
+        let mut position = 0;
+
+        iter::from_fn(move || {
+            while position < self.arena.len() {
+                let clause_ref = ClauseRef(position as u32);
+                position += HEADER_SIZE + self.len(clause_ref);
+
+                if !self.is_deleted(clause_ref) && !self.is_xor_reason(clause_ref) {
+                    return Some(clause_ref);
+                }
+            }
+
+            None
+        })
+    }
+
+    /// Fraction of the arena taken by deleted clauses.
+    pub fn garbage_fraction(&self) -> f64 {
+        if self.arena.is_empty() {
+            0.0
+        } else {
+            self.garbage as f64 / self.arena.len() as f64
+        }
+    }
+
+    /// Compacts the arena, dropping the deleted clauses. The others keep their order, but their
+    /// references change: the new ones are given by the returned relocation.
+    pub fn collect_garbage(&mut self) -> Relocation {
+        let mut relocated_refs = Vec::new();
+        let mut position = 0;
+        let mut new_position = 0;
+
+        while position < self.arena.len() {
+            let clause_ref = ClauseRef(position as u32);
+            let size = HEADER_SIZE + self.len(clause_ref);
+
+            if !self.is_deleted(clause_ref) {
+                self.arena.copy_within(position..position + size, new_position);
+                relocated_refs.push((clause_ref, ClauseRef(new_position as u32)));
+                new_position += size;
+            }
+            position += size;
+        }
+
+        self.arena.truncate(new_position);
+        self.arena.shrink_to_fit();
+        self.garbage = 0;
+
+        Relocation(relocated_refs)
+    }
+}
+
+impl fmt::Display for ClauseDb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let formatted_clauses: Vec<_> = self.iter()
+            .map(|clause_ref| {
+                let formatted_lits: Vec<_> = self.lits(clause_ref).iter().map(|lit| format!("{}", lit)).collect();
+                format!("\t{}: {{{}}}", clause_ref.0, formatted_lits.join(", "))
+            })
+            .collect();
+
+        write!(f, "{{\n{}\n}}", formatted_clauses.join("\n"))
+    }
+}
+
+/// Old and new references of the clauses kept by a garbage collection, ordered by old reference.
+pub(crate) struct Relocation(Vec<(ClauseRef, ClauseRef)>);
+
+impl Relocation {
+    /// New reference of the clause at `clause_ref`, `None` if it was deleted.
+    pub fn get(&self, clause_ref: ClauseRef) -> Option<ClauseRef> {
+        self.0.binary_search_by_key(&clause_ref, |&(old_ref, _)| old_ref)
+            .ok()
+            .map(|index| self.0[index].1)
+    }
+}
@@ -1,6 +1,8 @@
 use std::fmt;
 
+// Transparent, for the clause database to store literals as plain words
 #[derive(Ord, PartialOrd, Eq, PartialEq, Copy, Clone, Hash)]
+#[repr(transparent)]
 pub struct Literal(i32);
 
 impl Literal {
@@ -11,13 +13,51 @@ impl Literal {
         }
     }
 
+    pub fn from_var(var: u32, positive: bool) -> Self {
+        if positive {
+            Literal(var as i32)
+        } else {
+            Literal(-(var as i32))
+        }
+    }
+
     pub fn complementary(&self) -> Literal {
-        Literal(-1 * self.0)
+        Literal(-self.0)
     }
 
     pub fn non_existent() -> Literal {
         Literal(0)
     }
+
+    #[inline]
+    pub fn var(&self) -> u32 {
+        self.0.unsigned_abs()
+    }
+
+    #[inline]
+    pub fn is_positive(&self) -> bool {
+        self.0 > 0
+    }
+
+    #[inline]
+    pub fn to_i32(&self) -> i32 {
+        self.0
+    }
+
+    /// Dense index of the literal, for tables indexed by literal: `2 * var` for the positive
+    /// literal of `var`, `2 * var + 1` for the negative one.
+    #[inline]
+    pub(crate) fn index(&self) -> usize {
+        (self.var() as usize) << 1 | (self.0 < 0) as usize
+    }
+}
+
+impl From<i32> for Literal {
+    fn from(lit: i32) -> Self {
+        assert_ne!(lit, 0, "0 is not a valid literal");
+
+        Literal(lit)
+    }
 }
 
 impl fmt::Debug for Literal {
@@ -25,7 +65,7 @@ impl fmt::Debug for Literal {
         if self.0 != 0 {
             write!(f, "{}", self.0)
         } else {
-            write!(f, "{}", "-")
+            write!(f, "-")
         }
     }
 }
@@ -35,7 +75,7 @@ impl fmt::Display for Literal {
         if self.0 != 0 {
             write!(f, "{}", self.0)
         } else {
-            write!(f, "{}", "-")
+            write!(f, "-")
         }
     }
 }
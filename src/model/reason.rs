@@ -0,0 +1,38 @@
+use model::ClauseDb;
+use model::ClauseRef;
+use model::Literal;
+use model::Relocation;
+
+/// Clause implying an assigned literal, or falsified by a conflict. Binary clauses are not part of
+/// the clause database: they are given by their literals, the implied one first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Reason {
+    Clause(ClauseRef),
+    Binary([Literal; 2]),
+}
+
+impl Reason {
+    pub fn lits<'a>(&'a self, clause_db: &'a ClauseDb) -> &'a [Literal] {
+        match self {
+            Reason::Clause(clause_ref) => clause_db.lits(*clause_ref),
+            Reason::Binary(lits) => lits,
+        }
+    }
+
+    /// Reference of the clause in the clause database, if it is not binary.
+    pub fn clause_ref(&self) -> Option<ClauseRef> {
+        match self {
+            Reason::Clause(clause_ref) => Some(*clause_ref),
+            Reason::Binary(_) => None,
+        }
+    }
+
+    /// The same reason after a garbage collection of the clause database, `None` if its clause was
+    /// deleted.
+    pub fn relocate(&self, relocation: &Relocation) -> Option<Reason> {
+        match self {
+            Reason::Clause(clause_ref) => relocation.get(*clause_ref).map(Reason::Clause),
+            Reason::Binary(_) => Some(*self),
+        }
+    }
+}
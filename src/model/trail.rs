@@ -0,0 +1,139 @@
+use model::Literal;
+use model::LiteralVec;
+use model::Reason;
+use model::Relocation;
+
+/// Assignments of the solver: the assigned literals in the order they were assigned, along with
+/// the value, decision level and implying clause of every variable, indexed by variable.
+#[derive(Debug)]
+pub(crate) struct Trail {
+    lits: LiteralVec,
+    values: Vec<Option<bool>>,
+    levels: Vec<u32>,
+    reasons: Vec<Option<Reason>>,
+    // Length of the trail when every decision level but 0 was opened
+    limits: Vec<usize>,
+}
+
+impl Trail {
+    pub fn new() -> Self {
+        Trail {
+            lits: LiteralVec::new(),
+            values: Vec::new(),
+            levels: Vec::new(),
+            reasons: Vec::new(),
+            limits: Vec::new(),
+        }
+    }
+
+    /// Assigned literals, in assignment order.
+    #[inline]
+    pub fn lits(&self) -> &[Literal] {
+        &self.lits
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.lits.len()
+    }
+
+    #[inline]
+    pub fn decision_level(&self) -> u32 {
+        self.limits.len() as u32
+    }
+
+    pub fn new_decision_level(&mut self) {
+        self.limits.push(self.lits.len());
+    }
+
+    /// Length of the trail when `level`, which must be open, was opened.
+    pub fn limit(&self, level: u32) -> usize {
+        if level == 0 {
+            0
+        } else {
+            self.limits[level as usize - 1]
+        }
+    }
+
+    #[inline]
+    pub fn value(&self, lit: Literal) -> Option<bool> {
+        match self.values.get(lit.var() as usize) {
+            Some(&Some(value)) => Some(value == lit.is_positive()),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_true(&self, lit: Literal) -> bool {
+        self.value(lit) == Some(true)
+    }
+
+    #[inline]
+    pub fn is_false(&self, lit: Literal) -> bool {
+        self.value(lit) == Some(false)
+    }
+
+    /// Decision level of the assigned variable `var`.
+    #[inline]
+    pub fn level(&self, var: u32) -> u32 {
+        self.levels[var as usize]
+    }
+
+    /// Clause implying the value of the assigned variable `var`, `None` for decisions.
+    #[inline]
+    pub fn reason(&self, var: u32) -> Option<Reason> {
+        self.reasons[var as usize]
+    }
+
+    /// Assigns `lit` at `level`, which can be lower than the current one with chronological
+    /// backtracking.
+    pub fn assign(&mut self, lit: Literal, level: u32, reason: Option<Reason>) {
+        let var = lit.var() as usize;
+
+        if var >= self.values.len() {
+            self.values.resize(var + 1, None);
+            self.levels.resize(var + 1, 0);
+            self.reasons.resize(var + 1, None);
+        }
+
+        self.values[var] = Some(lit.is_positive());
+        self.levels[var] = level;
+        self.reasons[var] = reason;
+        self.lits.push(lit);
+    }
+
+    /// Forgets the clauses implying the assigned literals. Only literals of level 0 must be left,
+    /// since they hold no matter what.
+    pub fn clear_reasons(&mut self) {
+        self.reasons.iter_mut().for_each(|reason| *reason = None);
+    }
+
+    /// Updates the reasons after a garbage collection of the clause database.
+    pub fn relocate_reasons(&mut self, relocation: &Relocation) {
+        for reason in self.reasons.iter_mut() {
+            *reason = reason.and_then(|reason| reason.relocate(relocation));
+        }
+    }
+
+    /// Unassigns the literals of the levels above `level` and returns them, latest assigned first.
+    /// Literals of lower levels assigned after them stay assigned, in the same order.
+    pub fn backtrack(&mut self, level: u32) -> LiteralVec {
+        let limit = self.limit(level + 1);
+        let mut unassigned_lits = LiteralVec::new();
+        let mut kept_lits = LiteralVec::new();
+
+        for lit in self.lits.drain(limit..).rev() {
+            if self.levels[lit.var() as usize] > level {
+                self.values[lit.var() as usize] = None;
+                unassigned_lits.push(lit);
+            } else {
+                kept_lits.push(lit);
+            }
+        }
+
+        self.lits.extend(kept_lits.into_iter().rev());
+        self.limits.truncate(level as usize);
+
+        unassigned_lits
+    }
+}
@@ -0,0 +1,106 @@
+use model::Literal;
+use model::LiteralSet;
+use model::LiteralVec;
+
+// Longer constraints are cut before being turned into clauses, which are exponentially many
+const MAX_CLAUSAL_LENGTH: usize = 4;
+
+/// XOR constraint: the number of its variables that are true must have the given parity, odd when
+/// it is true. Variables repeated an even number of times cancel out.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Xor {
+    vars: Vec<u32>,
+    parity: bool,
+}
+
+impl Xor {
+    pub fn new(vars: &[u32], parity: bool) -> Self {
+        let mut sorted_vars = vars.to_vec();
+        sorted_vars.sort_unstable();
+
+        let mut xor_vars: Vec<u32> = Vec::with_capacity(sorted_vars.len());
+        for var in sorted_vars {
+            if xor_vars.last() == Some(&var) {
+                xor_vars.pop();
+            } else {
+                xor_vars.push(var);
+            }
+        }
+
+        Xor {
+            vars: xor_vars,
+            parity,
+        }
+    }
+
+    /// XOR of `lits` as in CryptoMiniSat: `x1 ⊕ ¬x2 ⊕ x3` is true, each negative literal flipping
+    /// the parity.
+    pub fn from_lits(lits: &[Literal]) -> Self {
+        let vars: Vec<u32> = lits.iter().map(|lit| lit.var()).collect();
+        let negative_lits = lits.iter().filter(|lit| !lit.is_positive()).count();
+
+        Xor::new(&vars, negative_lits % 2 == 0)
+    }
+
+    pub fn vars(&self) -> &[u32] {
+        &self.vars
+    }
+
+    pub fn parity(&self) -> bool {
+        self.parity
+    }
+
+    pub fn is_satisfied_by(&self, model: &LiteralSet) -> bool {
+        let true_vars = self.vars.iter().filter(|&&var| model.contains(&Literal::from_var(var, true))).count();
+
+        (true_vars % 2 == 1) == self.parity
+    }
+
+    /// Clauses equivalent to the constraint once the fresh variables `num_vars + 1, ...` they
+    /// introduce are projected out, `num_vars` being updated. Constraints of more than
+    /// `MAX_CLAUSAL_LENGTH` variables are first cut into a chain of shorter ones linked by fresh
+    /// variables, as in the Tseitin encoding, each holding the parity of the variables before it.
+    pub fn clauses(&self, num_vars: &mut u32) -> Vec<LiteralVec> {
+        self.cut(num_vars).iter().flat_map(|xor| xor.expanded_clauses()).collect()
+    }
+
+    fn cut(&self, num_vars: &mut u32) -> Vec<Xor> {
+        let mut xors = Vec::new();
+        let mut vars: &[u32] = &self.vars;
+        let mut link_var = None;
+
+        while link_var.iter().count() + vars.len() > MAX_CLAUSAL_LENGTH {
+            let num_taken_vars = MAX_CLAUSAL_LENGTH - 1 - link_var.iter().count();
+            *num_vars += 1;
+
+            let mut xor_vars: Vec<u32> = link_var.into_iter().chain(vars[..num_taken_vars].iter().cloned()).collect();
+            xor_vars.push(*num_vars);
+            xors.push(Xor::new(&xor_vars, false));
+
+            link_var = Some(*num_vars);
+            vars = &vars[num_taken_vars..];
+        }
+
+        let xor_vars: Vec<u32> = link_var.into_iter().chain(vars.iter().cloned()).collect();
+        xors.push(Xor::new(&xor_vars, self.parity));
+
+        xors
+    }
+
+    /// One clause for each assignment of the variables with the wrong parity, which it rules out.
+    /// There are `2^(n-1)` of them for `n` variables.
+    fn expanded_clauses(&self) -> Vec<LiteralVec> {
+        if self.vars.is_empty() {
+            return if self.parity { vec![Vec::new()] } else { Vec::new() };
+        }
+
+        (0..1u64 << self.vars.len())
+            .filter(|assignment| (assignment.count_ones() % 2 == 1) != self.parity)
+            .map(|assignment| {
+                self.vars.iter().enumerate()
+                    .map(|(position, &var)| Literal::from_var(var, assignment >> position & 1 == 0))
+                    .collect()
+            })
+            .collect()
+    }
+}
@@ -1,32 +1,86 @@
 use model::ClauseSet;
+use model::ClauseVec;
 use dimacs::parse_dimacs as parse_file_content;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::Read;
 use dimacs::Instance;
 use model::Clause;
+use model::Literal;
+use model::Xor;
 
 pub fn parse_dimacs_file(filename: &str) -> Result<ClauseSet, String> {
+    let mut clause_set = ClauseSet::default();
+
+    parse_dimacs_clauses(filename)?.into_iter().for_each(|clause| {
+        clause_set.insert(clause);
+    });
+
+    Ok(clause_set)
+}
+
+/// Parses the clauses of a .cnf file, keeping their order and any duplicates. XOR constraints are
+/// left out.
+pub fn parse_dimacs_clauses(filename: &str) -> Result<ClauseVec, String> {
     let content = read_file(filename)?;
+    let content: String = content.lines()
+        .filter(|line| !is_xor_line(line))
+        .map(|line| format!("{}\n", line))
+        .collect();
+
+    // The parser rejects a header followed by no clause, as files of XOR constraints only are left
+    let is_blank = |line: &str| line.trim().is_empty() || line.starts_with('c') || line.starts_with('p');
+    if content.lines().all(is_blank) {
+        return Ok(ClauseVec::new());
+    }
 
     let instance = parse_file_content(&content)
         .map_err(|parse_err| format!("Error while parsing: {:?}", parse_err))?;
 
-    let mut clause_set = ClauseSet::default();
-
     match instance {
         Instance::Cnf { clauses, .. } => {
-            clauses.iter().map(|clause| Clause::from_dimacs_clause(clause))
-                .for_each(|clause| {
-                    clause_set.insert(clause);
-                });
-
-            Ok(clause_set)
+            Ok(clauses.iter().map(Clause::from_dimacs_clause).collect())
         },
         _ => Err(String::from("Only .cnf instances are supported")),
     }
 }
 
+/// Number of variables declared by the `p cnf` header of a .cnf file.
+pub fn parse_dimacs_num_vars(filename: &str) -> Result<u32, String> {
+    read_file(filename)?.lines()
+        .find(|line| line.starts_with('p'))
+        .and_then(|line| line.split_whitespace().nth(2))
+        .and_then(|token| token.parse().ok())
+        .ok_or_else(|| format!("Missing or invalid p cnf header: {}", filename))
+}
+
+/// Parses the XOR constraints of a .cnf file, written on lines starting with `x` as in
+/// CryptoMiniSat, e.g. `x1 -2 3 0` for `x1 ⊕ ¬x2 ⊕ x3`.
+pub fn parse_dimacs_xors(filename: &str) -> Result<Vec<Xor>, String> {
+    read_file(filename)?.lines()
+        .filter(|line| is_xor_line(line))
+        .map(parse_xor_line)
+        .collect()
+}
+
+fn is_xor_line(line: &str) -> bool {
+    line.trim_start().starts_with('x')
+}
+
+fn parse_xor_line(line: &str) -> Result<Xor, String> {
+    let lits = line.trim_start()[1..].split_whitespace()
+        .map(|token| token.parse().map_err(|_err| format!("Invalid literal in XOR constraint: {}", token)))
+        .collect::<Result<Vec<i32>, String>>()?;
+
+    match lits.split_last() {
+        Some((0, lits)) if !lits.contains(&0) => {
+            let xor_lits: Vec<Literal> = lits.iter().map(|&lit| Literal::from(lit)).collect();
+            Ok(Xor::from_lits(&xor_lits))
+        },
+        _ => Err(format!("XOR constraints must end with a single 0: {}", line)),
+    }
+}
+
 fn read_file(filename: &str) -> Result<String, String> {
     let file = File::open(filename)
         .map_err(|_err| format!("Cannot open file: {}", filename))?;
@@ -0,0 +1,168 @@
+use model::Literal;
+use random::Random;
+
+/// Value a decided variable is assigned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Polarity {
+    /// The value the variable had last. Variables never assigned before get the value of their
+    /// literal occurring the most in the clauses, weighted by clause length, or false on ties.
+    Saved,
+    False,
+    True,
+    Random,
+    /// The value the variable had in the largest assignment without conflicts since the last
+    /// restart, else its saved value.
+    Target,
+    /// The value the variable had in the largest assignment without conflicts so far, else its
+    /// saved value.
+    Best,
+}
+
+/// Parses the polarity called `name`: one of `saved`, `false`, `true`, `random`, `target` or
+/// `best`.
+pub fn polarity_from_name(name: &str) -> Result<Polarity, String> {
+    match name {
+        "saved" => Ok(Polarity::Saved),
+        "false" => Ok(Polarity::False),
+        "true" => Ok(Polarity::True),
+        "random" => Ok(Polarity::Random),
+        "target" => Ok(Polarity::Target),
+        "best" => Ok(Polarity::Best),
+        _ => Err(format!("Unknown polarity: {}", name)),
+    }
+}
+
+/**
+ * Values remembered for every variable, used to pick the polarity of decisions: the saved phases
+ * of phase saving, and the target and best phases of CaDiCaL. Until a variable is assigned, its
+ * saved phase follows the Jeroslow-Wang weights of its literals, where every clause counts for
+ * `2^-n` with `n` literals.
+ *
+ * see https://doi.org/10.1007/978-3-540-72788-0_28
+ * see http://fmv.jku.at/papers/BiereFleury-POS20.pdf
+ * see https://doi.org/10.1007/BF01531077
+ */
+pub(crate) struct Phases {
+    polarity: Polarity,
+    saved: Vec<Option<bool>>,
+    // Weight of the positive literal of every variable minus the one of its negative literal
+    occurrence_weights: Vec<f64>,
+    target: Vec<Option<bool>>,
+    best: Vec<Option<bool>>,
+    target_size: usize,
+    best_size: usize,
+    random: Random,
+}
+
+impl Phases {
+    pub fn new(polarity: Polarity) -> Self {
+        Phases {
+            polarity,
+            saved: Vec::new(),
+            occurrence_weights: Vec::new(),
+            target: Vec::new(),
+            best: Vec::new(),
+            target_size: 0,
+            best_size: 0,
+            random: Random::new(0),
+        }
+    }
+
+    pub fn set_polarity(&mut self, polarity: Polarity) {
+        self.polarity = polarity;
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.random = Random::new(seed);
+    }
+
+    /// Remembers the value of the variable of `lit`, just assigned.
+    #[inline]
+    pub fn save(&mut self, lit: Literal) {
+        let var = lit.var() as usize;
+
+        if var >= self.saved.len() {
+            self.saved.resize(var + 1, None);
+        }
+        self.saved[var] = Some(lit.is_positive());
+    }
+
+    /// Counts the literals of a clause of the formula towards the phases of variables never
+    /// assigned.
+    pub fn add_clause(&mut self, lits: &[Literal]) {
+        let weight = 0.5f64.powi(lits.len().min(i32::MAX as usize) as i32);
+
+        for lit in lits {
+            let var = lit.var() as usize;
+
+            if var >= self.occurrence_weights.len() {
+                self.occurrence_weights.resize(var + 1, 0.0);
+            }
+            self.occurrence_weights[var] += if lit.is_positive() { weight } else { -weight };
+        }
+    }
+
+    /// Forgets the clauses counted so far, before the ones of a simplified formula are counted.
+    pub fn clear_occurrences(&mut self) {
+        self.occurrence_weights.clear();
+    }
+
+    /// Whether the polarity relies on the assignments given to `on_conflict`, which only the target
+    /// and best phases do.
+    pub fn tracks_conflicts(&self) -> bool {
+        self.polarity == Polarity::Target || self.polarity == Polarity::Best
+    }
+
+    /// Called before backtracking out of a conflict, with `lits`, the `size` literals assigned below
+    /// the conflict level, which do not falsify any clause.
+    pub fn on_conflict<I: Iterator<Item=Literal>>(&mut self, lits: I, size: usize) {
+        if size <= self.target_size {
+            return;
+        }
+
+        let lits: Vec<Literal> = lits.collect();
+        Phases::remember(&mut self.target, &lits);
+        self.target_size = size;
+
+        if size > self.best_size {
+            Phases::remember(&mut self.best, &lits);
+            self.best_size = size;
+        }
+    }
+
+    /// Target phases only last until the next restart.
+    pub fn on_restart(&mut self) {
+        self.target.clear();
+        self.target_size = 0;
+    }
+
+    /// Literal deciding `var` according to the polarity.
+    pub fn decision_lit(&mut self, var: u32) -> Literal {
+        let saved = self.saved.get(var as usize).cloned().unwrap_or(None)
+            .unwrap_or_else(|| self.occurrence_weights.get(var as usize).is_some_and(|&weight| weight > 0.0));
+
+        let value = match self.polarity {
+            Polarity::Saved => saved,
+            Polarity::False => false,
+            Polarity::True => true,
+            Polarity::Random => self.random.next_bool(),
+            Polarity::Target => self.target.get(var as usize).cloned().unwrap_or(None).unwrap_or(saved),
+            Polarity::Best => self.best.get(var as usize).cloned().unwrap_or(None).unwrap_or(saved),
+        };
+
+        Literal::from_var(var, value)
+    }
+
+    fn remember(phases: &mut Vec<Option<bool>>, lits: &[Literal]) {
+        phases.clear();
+
+        for lit in lits {
+            let var = lit.var() as usize;
+
+            if var >= phases.len() {
+                phases.resize(var + 1, None);
+            }
+            phases[var] = Some(lit.is_positive());
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use fnv::FnvHashSet;
+use model::Literal;
+use model::LiteralVec;
+use preprocessing::OccurrenceLists;
+use proof::Proof;
+use std::mem;
+
+// Variables are kept when eliminating them would add longer resolvents, or when they occur in more
+// clauses, which makes resolving all of them too costly
+const MAX_RESOLVENT_LENGTH: usize = 20;
+const MAX_OCCURRENCES: usize = 100;
+
+/// Clauses removed by variable elimination, in elimination order, each along with the literal of
+/// its eliminated variable. Models of the remaining clauses are extended to the eliminated
+/// variables by going through them backwards.
+#[derive(Debug, Default)]
+pub(crate) struct EliminationStack {
+    clauses: Vec<(Literal, LiteralVec)>,
+}
+
+impl EliminationStack {
+    pub fn new() -> Self {
+        EliminationStack::default()
+    }
+
+    pub fn push(&mut self, pivot: Literal, lits: LiteralVec) {
+        self.clauses.push((pivot, lits));
+    }
+
+    /// Assigns the eliminated variables in `values`, indexed by variable, so that every removed
+    /// clause is satisfied: whenever one is not, its eliminated literal is made true.
+    pub fn extend_model(&self, values: &mut [bool]) {
+        for (pivot, lits) in self.clauses.iter().rev() {
+            if !lits.iter().any(|lit| values[lit.var() as usize] == lit.is_positive()) {
+                values[pivot.var() as usize] = pivot.is_positive();
+            }
+        }
+    }
+
+    /// Takes back the removed clauses of the eliminated `var`.
+    pub fn restore(&mut self, var: u32) -> Vec<LiteralVec> {
+        let (restored, kept): (Vec<_>, Vec<_>) = mem::take(&mut self.clauses).into_iter()
+            .partition(|(pivot, _)| pivot.var() == var);
+        self.clauses = kept;
+
+        restored.into_iter().map(|(_, lits)| lits).collect()
+    }
+}
+
+/**
+ * Bounded variable elimination, as in SatELite: a variable is eliminated by replacing the clauses
+ * it occurs in with all their non-tautological resolvents on it, as long as that does not make
+ * more clauses. Variables are tried by increasing number of resolutions, and again whenever the
+ * clauses they occur in change, until none can be eliminated. `frozen_vars` are never eliminated.
+ *
+ * Returns the eliminated variables, whose clauses are moved from `occurrence_lists` to `stack`.
+ * Resolvents are logged to `proof` before the clauses they replace are deleted.
+ *
+ * see https://doi.org/10.1007/11499107_5
+ */
+pub(crate) fn eliminate_vars(occurrence_lists: &mut OccurrenceLists, frozen_vars: &FnvHashSet<u32>,
+                             stack: &mut EliminationStack, mut proof: Option<&mut Proof>) -> Vec<u32> {
+    let mut eliminated_vars = Vec::new();
+
+    let mut candidates: FnvHashSet<u32> = occurrence_lists.positions().into_iter()
+        .flat_map(|position| occurrence_lists.clause(position).to_vec())
+        .map(|lit| lit.var())
+        .collect();
+
+    while !candidates.is_empty() {
+        let mut ordered_candidates: Vec<(usize, u32)> = candidates.drain()
+            .filter(|var| !frozen_vars.contains(var))
+            .map(|var| {
+                let lit = Literal::from_var(var, true);
+                let resolutions = occurrence_lists.occurrences(lit).len()
+                    * occurrence_lists.occurrences(lit.complementary()).len();
+
+                (resolutions, var)
+            })
+            .collect();
+        ordered_candidates.sort();
+
+        for (_, var) in ordered_candidates {
+            let resolvents = match bounded_resolvents(occurrence_lists, var) {
+                Some(resolvents) => resolvents,
+                None => continue,
+            };
+
+            for resolvent in resolvents {
+                if let Some(proof) = proof.as_mut() {
+                    proof.add_clause(&resolvent);
+                }
+                occurrence_lists.add(resolvent);
+            }
+
+            let lit = Literal::from_var(var, true);
+            let mut positions = occurrence_lists.occurrences(lit).to_vec();
+            positions.extend_from_slice(occurrence_lists.occurrences(lit.complementary()));
+
+            for position in positions {
+                let lits = occurrence_lists.remove(position);
+                if let Some(proof) = proof.as_mut() {
+                    proof.delete_clause(&lits);
+                }
+
+                candidates.extend(lits.iter().map(|lit| lit.var()).filter(|&other_var| other_var != var));
+                let pivot = *lits.iter().find(|lit| lit.var() == var).unwrap();
+                stack.push(pivot, lits);
+            }
+
+            candidates.remove(&var);
+            eliminated_vars.push(var);
+        }
+    }
+
+    eliminated_vars
+}
+
+/// Non-tautological resolvents on `var` of the clauses it occurs in, `None` if there are more of
+/// them than clauses, or if some are too long.
+fn bounded_resolvents(occurrence_lists: &mut OccurrenceLists, var: u32) -> Option<Vec<LiteralVec>> {
+    let lit = Literal::from_var(var, true);
+    let positive_positions = occurrence_lists.occurrences(lit).to_vec();
+    let negative_positions = occurrence_lists.occurrences(lit.complementary()).to_vec();
+
+    let num_clauses = positive_positions.len() + negative_positions.len();
+    if num_clauses == 0 || num_clauses > MAX_OCCURRENCES {
+        return None;
+    }
+
+    let mut resolvents = Vec::new();
+
+    for &positive_position in &positive_positions {
+        for &negative_position in &negative_positions {
+            let resolvent = resolve(occurrence_lists.clause(positive_position),
+                                    occurrence_lists.clause(negative_position), var);
+
+            if let Some(resolvent) = resolvent {
+                if resolvent.len() > MAX_RESOLVENT_LENGTH || resolvents.len() == num_clauses {
+                    return None;
+                }
+                resolvents.push(resolvent);
+            }
+        }
+    }
+
+    Some(resolvents)
+}
+
+/// Resolvent on `var` of two clauses holding its two literals, `None` if it is a tautology.
+fn resolve(lits_a: &[Literal], lits_b: &[Literal], var: u32) -> Option<LiteralVec> {
+    let mut resolvent: LiteralVec = lits_a.iter().cloned().filter(|lit| lit.var() != var).collect();
+
+    for &lit in lits_b.iter().filter(|lit| lit.var() != var) {
+        if resolvent.contains(&lit.complementary()) {
+            return None;
+        }
+        if !resolvent.contains(&lit) {
+            resolvent.push(lit);
+        }
+    }
+
+    Some(resolvent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NUM_VARS: u32 = 5;
+
+    fn clauses(dimacs_clauses: &[&[i32]]) -> Vec<LiteralVec> {
+        dimacs_clauses.iter().map(|lits| lits.iter().map(|&lit| Literal::from(lit)).collect()).collect()
+    }
+
+    fn satisfies(values: &[bool], clauses: &[LiteralVec]) -> bool {
+        clauses.iter().all(|lits| lits.iter().any(|lit| values[lit.var() as usize] == lit.is_positive()))
+    }
+
+    #[test]
+    fn extended_models_satisfy_the_clauses_before_elimination() {
+        let clauses = clauses(&[&[1, 2], &[-1, 3], &[-2, 3, 4], &[-3, 5], &[-4, -5], &[2, -5]]);
+        let mut occurrence_lists = OccurrenceLists::new(clauses.clone());
+        let mut stack = EliminationStack::new();
+
+        let eliminated_vars = eliminate_vars(&mut occurrence_lists, &FnvHashSet::default(), &mut stack, None);
+        let remaining_clauses = occurrence_lists.into_clauses();
+        assert!(!eliminated_vars.is_empty());
+
+        let mut num_models = 0;
+        for assignment in 0..1u32 << NUM_VARS {
+            let mut values: Vec<bool> = (0..=NUM_VARS).map(|var| var > 0 && assignment >> (var - 1) & 1 == 1).collect();
+            if !satisfies(&values, &remaining_clauses) {
+                continue;
+            }
+
+            stack.extend_model(&mut values);
+            assert!(satisfies(&values, &clauses), "{:?} does not satisfy the original clauses", values);
+            num_models += 1;
+        }
+        assert!(num_models > 0);
+    }
+}
@@ -0,0 +1,228 @@
+use fnv::FnvHashMap;
+use fnv::FnvHashSet;
+use model::Literal;
+use model::LiteralVec;
+use preprocessing::EliminationStack;
+use proof::Proof;
+
+/**
+ * Equivalent literals, found as the strongly connected components of the binary implication graph,
+ * where every binary clause `a ∨ b` gives the implications `¬a → b` and `¬b → a`. The literals of a
+ * component all imply each other, so each component gets a representative: a literal of a frozen
+ * variable if there is one, else the one of the lowest variable. The other variables of the
+ * component, unless frozen, are to be replaced with it.
+ *
+ * Returns the literal every replaced variable is equivalent to, or `Err` with a literal that is
+ * equivalent to its complementary, which makes the clauses UNSAT.
+ *
+ * see https://doi.org/10.1137/0201010
+ */
+pub(crate) fn find_equivalences(clauses: &[LiteralVec], frozen_vars: &FnvHashSet<u32>)
+                                -> Result<FnvHashMap<u32, Literal>, Literal> {
+    let mut substitutions = FnvHashMap::default();
+
+    for component in strongly_connected_components(clauses) {
+        let mut vars = FnvHashSet::default();
+        if let Some(&lit) = component.iter().find(|lit| !vars.insert(lit.var())) {
+            return Err(lit);
+        }
+
+        let representative = *component.iter()
+            .min_by_key(|lit| (!frozen_vars.contains(&lit.var()), lit.var()))
+            .unwrap();
+
+        // The component of the complementary literals yields the same substitutions
+        for lit in component.into_iter().filter(|lit| lit.is_positive()) {
+            if lit.var() != representative.var() && !frozen_vars.contains(&lit.var()) {
+                substitutions.insert(lit.var(), representative);
+            }
+        }
+    }
+
+    Ok(substitutions)
+}
+
+/// Replaces the variables of `substitutions` with the literals they are equivalent to, dropping
+/// the clauses that become tautologies. The equivalences are moved to `stack`, which gives the
+/// replaced variables their values in models. Changed clauses are all logged to `proof` before the
+/// original ones are deleted, since the binary clauses making them equivalent are among those.
+pub(crate) fn substitute_equivalences(clauses: Vec<LiteralVec>, substitutions: &FnvHashMap<u32, Literal>,
+                                      stack: &mut EliminationStack, mut proof: Option<&mut Proof>) -> Vec<LiteralVec> {
+    let substituted_clauses: Vec<Option<LiteralVec>> = clauses.iter()
+        .map(|lits| substituted(lits, substitutions))
+        .collect();
+
+    if let Some(proof) = proof.as_mut() {
+        let changed_clauses = || clauses.iter().zip(&substituted_clauses)
+            .filter(|&(lits, substituted_lits)| substituted_lits.as_ref() != Some(lits));
+
+        changed_clauses().for_each(|(_, substituted_lits)| substituted_lits.iter().for_each(|lits| proof.add_clause(lits)));
+        changed_clauses().for_each(|(lits, _)| proof.delete_clause(lits));
+    }
+
+    let mut replaced_vars: Vec<u32> = substitutions.keys().cloned().collect();
+    replaced_vars.sort();
+
+    for var in replaced_vars {
+        let lit = Literal::from_var(var, true);
+        let equivalent_lit = substitutions[&var];
+
+        stack.push(lit, vec![lit, equivalent_lit.complementary()]);
+        stack.push(lit.complementary(), vec![lit.complementary(), equivalent_lit]);
+    }
+
+    substituted_clauses.into_iter().flatten().collect()
+}
+
+/// Literals of a clause once the variables of `substitutions` are replaced, `None` if it becomes a
+/// tautology.
+fn substituted(lits: &[Literal], substitutions: &FnvHashMap<u32, Literal>) -> Option<LiteralVec> {
+    let mut substituted_lits = LiteralVec::with_capacity(lits.len());
+
+    for &lit in lits {
+        let lit = match substitutions.get(&lit.var()) {
+            Some(&equivalent_lit) if lit.is_positive() => equivalent_lit,
+            Some(&equivalent_lit) => equivalent_lit.complementary(),
+            None => lit,
+        };
+
+        if substituted_lits.contains(&lit.complementary()) {
+            return None;
+        }
+        if !substituted_lits.contains(&lit) {
+            substituted_lits.push(lit);
+        }
+    }
+
+    Some(substituted_lits)
+}
+
+/// Strongly connected components of more than one literal in the binary implication graph of
+/// `clauses`, found with Tarjan's algorithm, without recursion so that long implication chains
+/// cannot overflow the stack.
+fn strongly_connected_components(clauses: &[LiteralVec]) -> Vec<LiteralVec> {
+    let num_vars = clauses.iter().flatten().map(|lit| lit.var()).max().unwrap_or(0);
+    let num_lits = 2 * (num_vars as usize + 1);
+    // Literals implied by every literal, indexed by literal
+    let mut implications = vec![Vec::new(); num_lits];
+
+    for lits in clauses.iter().filter(|lits| lits.len() == 2) {
+        implications[lits[0].complementary().index()].push(lits[1]);
+        implications[lits[1].complementary().index()].push(lits[0]);
+    }
+
+    // Visit order of every literal, starting from 1, and lowest visit order it can reach
+    let mut orders = vec![0; num_lits];
+    let mut low_links = vec![0; num_lits];
+    let mut on_stack = vec![false; num_lits];
+    let mut stack = Vec::new();
+    let mut next_order = 1;
+    let mut components = Vec::new();
+
+    let lits = (1..=num_vars).flat_map(|var| vec![Literal::from_var(var, true), Literal::from_var(var, false)]);
+
+    for root in lits {
+        if implications[root.index()].is_empty() || orders[root.index()] != 0 {
+            continue;
+        }
+
+        // Literals being visited, along with the position of their next implication to follow
+        let mut path = vec![(root, 0)];
+        orders[root.index()] = next_order;
+        low_links[root.index()] = next_order;
+        next_order += 1;
+        stack.push(root);
+        on_stack[root.index()] = true;
+
+        while let Some(&(lit, position)) = path.last() {
+            if let Some(&implied_lit) = implications[lit.index()].get(position) {
+                path.last_mut().unwrap().1 += 1;
+
+                let index = implied_lit.index();
+                if orders[index] == 0 {
+                    orders[index] = next_order;
+                    low_links[index] = next_order;
+                    next_order += 1;
+                    stack.push(implied_lit);
+                    on_stack[index] = true;
+                    path.push((implied_lit, 0));
+                } else if on_stack[index] {
+                    low_links[lit.index()] = low_links[lit.index()].min(orders[index]);
+                }
+                continue;
+            }
+
+            path.pop();
+            if let Some(&(parent, _)) = path.last() {
+                low_links[parent.index()] = low_links[parent.index()].min(low_links[lit.index()]);
+            }
+
+            if low_links[lit.index()] == orders[lit.index()] {
+                let mut component = LiteralVec::new();
+
+                while let Some(component_lit) = stack.pop() {
+                    on_stack[component_lit.index()] = false;
+                    component.push(component_lit);
+
+                    if component_lit == lit {
+                        break;
+                    }
+                }
+
+                if component.len() > 1 {
+                    components.push(component);
+                }
+            }
+        }
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NUM_VARS: u32 = 5;
+
+    fn clauses(dimacs_clauses: &[&[i32]]) -> Vec<LiteralVec> {
+        dimacs_clauses.iter().map(|lits| lits.iter().map(|&lit| Literal::from(lit)).collect()).collect()
+    }
+
+    fn satisfies(values: &[bool], clauses: &[LiteralVec]) -> bool {
+        clauses.iter().all(|lits| lits.iter().any(|lit| values[lit.var() as usize] == lit.is_positive()))
+    }
+
+    #[test]
+    fn extended_models_satisfy_the_clauses_before_substitution() {
+        // 1 and 2 are equivalent, so are 3 and -4
+        let clauses = clauses(&[&[-1, 2], &[1, -2], &[3, 4], &[-3, -4], &[1, 3, 5], &[-2, -4, -5], &[2, -5]]);
+        let substitutions = find_equivalences(&clauses, &FnvHashSet::default()).unwrap();
+        assert_eq!(substitutions.get(&2), Some(&Literal::from(1)));
+        assert_eq!(substitutions.get(&4), Some(&Literal::from(-3)));
+
+        let mut stack = EliminationStack::new();
+        let substituted_clauses = substitute_equivalences(clauses.clone(), &substitutions, &mut stack, None);
+        assert!(substituted_clauses.iter().flatten().all(|lit| lit.var() != 2 && lit.var() != 4));
+
+        let mut num_models = 0;
+        for assignment in 0..1u32 << NUM_VARS {
+            let mut values: Vec<bool> = (0..=NUM_VARS).map(|var| var > 0 && assignment >> (var - 1) & 1 == 1).collect();
+            if !satisfies(&values, &substituted_clauses) {
+                continue;
+            }
+
+            stack.extend_model(&mut values);
+            assert!(satisfies(&values, &clauses), "{:?} does not satisfy the original clauses", values);
+            num_models += 1;
+        }
+        assert!(num_models > 0);
+    }
+
+    #[test]
+    fn literal_equivalent_to_its_complementary_is_found() {
+        let clauses = clauses(&[&[-1, 2], &[1, -2], &[1, 2], &[-1, -2]]);
+
+        assert!(find_equivalences(&clauses, &FnvHashSet::default()).is_err());
+    }
+}
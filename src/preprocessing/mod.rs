@@ -0,0 +1,13 @@
+mod elimination;
+mod equivalences;
+mod occurrences;
+mod subsumption;
+mod xors;
+
+pub(crate) use self::elimination::eliminate_vars;
+pub(crate) use self::elimination::EliminationStack;
+pub(crate) use self::equivalences::find_equivalences;
+pub(crate) use self::equivalences::substitute_equivalences;
+pub(crate) use self::occurrences::OccurrenceLists;
+pub(crate) use self::subsumption::subsume_clauses;
+pub(crate) use self::xors::find_xors;
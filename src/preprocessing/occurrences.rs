@@ -0,0 +1,98 @@
+use model::Literal;
+use model::LiteralVec;
+
+/// Clauses being simplified, along with the clauses every literal occurs in. Removed clauses leave
+/// an empty slot behind, and are dropped from the occurrence lists the next time they are visited.
+pub(crate) struct OccurrenceLists {
+    clauses: Vec<Option<LiteralVec>>,
+    // Positions of the clauses every literal occurs in, indexed by literal
+    occurrences: Vec<Vec<usize>>,
+    signatures: Vec<u64>,
+}
+
+impl OccurrenceLists {
+    pub fn new(clauses: Vec<LiteralVec>) -> Self {
+        let mut occurrence_lists = OccurrenceLists {
+            clauses: Vec::with_capacity(clauses.len()),
+            occurrences: Vec::new(),
+            signatures: Vec::with_capacity(clauses.len()),
+        };
+
+        clauses.into_iter().for_each(|lits| { occurrence_lists.add(lits); });
+
+        occurrence_lists
+    }
+
+    pub fn add(&mut self, lits: LiteralVec) -> usize {
+        let position = self.clauses.len();
+
+        for lit in &lits {
+            if lit.index() >= self.occurrences.len() {
+                self.occurrences.resize(lit.index() + 2, Vec::new());
+            }
+            self.occurrences[lit.index()].push(position);
+        }
+        self.signatures.push(signature(&lits));
+        self.clauses.push(Some(lits));
+
+        position
+    }
+
+    /// Removes `lit` from the clause at `position`.
+    pub fn strengthen(&mut self, position: usize, lit: Literal) {
+        let lits = self.clauses[position].as_mut().expect("Removed clauses cannot be strengthened");
+        lits.retain(|&clause_lit| clause_lit != lit);
+        self.signatures[position] = signature(lits);
+        self.occurrences[lit.index()].retain(|&other_position| other_position != position);
+    }
+
+    pub fn remove(&mut self, position: usize) -> LiteralVec {
+        self.clauses[position].take().expect("Clauses are removed once")
+    }
+
+    /// Literals of the clause at `position`, which must not have been removed.
+    pub fn clause(&self, position: usize) -> &[Literal] {
+        self.clauses[position].as_ref().expect("Removed clauses have no literals")
+    }
+
+    pub fn is_removed(&self, position: usize) -> bool {
+        self.clauses[position].is_none()
+    }
+
+    /// Signature of the clause at `position`: a set of bits, one for each of its variables, such
+    /// that the signature of a clause is included in the one of any clause it subsumes.
+    pub fn signature(&self, position: usize) -> u64 {
+        self.signatures[position]
+    }
+
+    /// Positions of the clauses `lit` occurs in.
+    pub fn occurrences(&mut self, lit: Literal) -> &[usize] {
+        match self.occurrences.get_mut(lit.index()) {
+            Some(positions) => {
+                let clauses = &self.clauses;
+                positions.retain(|&position| clauses[position].is_some());
+                positions
+            },
+            None => &[],
+        }
+    }
+
+    /// Number of clauses `lit` occurs in, counting the removed ones not dropped from its occurrence
+    /// list yet.
+    pub fn num_occurrences(&self, lit: Literal) -> usize {
+        self.occurrences.get(lit.index()).map_or(0, |positions| positions.len())
+    }
+
+    /// Positions of the clauses not removed.
+    pub fn positions(&self) -> Vec<usize> {
+        (0..self.clauses.len()).filter(|&position| self.clauses[position].is_some()).collect()
+    }
+
+    pub fn into_clauses(self) -> Vec<LiteralVec> {
+        self.clauses.into_iter().flatten().collect()
+    }
+}
+
+fn signature(lits: &[Literal]) -> u64 {
+    lits.iter().fold(0, |signature, lit| signature | 1 << (lit.var() % 64))
+}
@@ -0,0 +1,100 @@
+use model::Literal;
+use preprocessing::OccurrenceLists;
+use proof::Proof;
+use std::collections::VecDeque;
+
+/**
+ * Backward subsumption and self-subsuming resolution. Every clause `c` is compared with the other
+ * clauses holding its least occurring variable: those containing `c` are deleted, and those
+ * containing `c` but for one literal, complementary, lose that literal. Signatures rule out most
+ * pairs without comparing their literals. Strengthened clauses are compared again.
+ *
+ * Only the clauses `is_removable` accepts are deleted or strengthened. Strengthened clauses are
+ * logged to `proof`, and so are deleted ones. Returns the number of deleted clauses and the
+ * number of strengthened ones.
+ *
+ * see https://doi.org/10.1007/11499107_5
+ */
+pub(crate) fn subsume_clauses<F>(occurrence_lists: &mut OccurrenceLists, is_removable: F,
+                                 mut proof: Option<&mut Proof>) -> (usize, usize) where F: Fn(usize) -> bool {
+    let mut subsumed_clauses = 0;
+    let mut strengthened_clauses = 0;
+    let mut queue: VecDeque<usize> = occurrence_lists.positions().into_iter().collect();
+
+    while let Some(position) = queue.pop_front() {
+        if occurrence_lists.is_removed(position) {
+            continue;
+        }
+
+        let lits = occurrence_lists.clause(position).to_vec();
+        let signature = occurrence_lists.signature(position);
+
+        let var = match lits.iter().map(|lit| lit.var()).min_by_key(|&var| num_occurrences(occurrence_lists, var)) {
+            Some(var) => var,
+            None => continue,
+        };
+        let lit = Literal::from_var(var, true);
+        let mut other_positions = occurrence_lists.occurrences(lit).to_vec();
+        other_positions.extend_from_slice(occurrence_lists.occurrences(lit.complementary()));
+
+        for other_position in other_positions {
+            if other_position == position || occurrence_lists.is_removed(other_position) || !is_removable(other_position) {
+                continue;
+            }
+
+            let other_lits = occurrence_lists.clause(other_position);
+            if other_lits.len() < lits.len() || signature & !occurrence_lists.signature(other_position) != 0 {
+                continue;
+            }
+
+            match subsumption(&lits, other_lits) {
+                Some(None) => {
+                    let other_lits = occurrence_lists.remove(other_position);
+                    if let Some(proof) = proof.as_mut() {
+                        proof.delete_clause(&other_lits);
+                    }
+                    subsumed_clauses += 1;
+                },
+                Some(Some(resolved_lit)) => {
+                    let other_lits = other_lits.to_vec();
+                    occurrence_lists.strengthen(other_position, resolved_lit.complementary());
+                    if let Some(proof) = proof.as_mut() {
+                        proof.add_clause(occurrence_lists.clause(other_position));
+                        proof.delete_clause(&other_lits);
+                    }
+                    strengthened_clauses += 1;
+                    queue.push_back(other_position);
+                },
+                None => {},
+            }
+        }
+    }
+
+    (subsumed_clauses, strengthened_clauses)
+}
+
+fn num_occurrences(occurrence_lists: &OccurrenceLists, var: u32) -> usize {
+    let lit = Literal::from_var(var, true);
+
+    occurrence_lists.num_occurrences(lit) + occurrence_lists.num_occurrences(lit.complementary())
+}
+
+/// `Some(None)` if `lits` subsumes `other_lits`, `Some(Some(lit))` if resolving them on `lit`
+/// gives a clause that does, the one `other_lits` becomes once its complementary is removed.
+fn subsumption(lits: &[Literal], other_lits: &[Literal]) -> Option<Option<Literal>> {
+    let mut resolved_lit = None;
+
+    for &lit in lits {
+        if other_lits.contains(&lit) {
+            continue;
+        }
+
+        if resolved_lit.is_none() && other_lits.contains(&lit.complementary()) {
+            resolved_lit = Some(lit);
+        } else {
+            return None;
+        }
+    }
+
+    Some(resolved_lit)
+}
@@ -0,0 +1,50 @@
+use fnv::FnvHashMap;
+use fnv::FnvHashSet;
+use model::LiteralVec;
+use model::Xor;
+
+// Longer XOR constraints take too many clauses to be written in CNF, while shorter ones are binary
+// clauses, better left to equivalent-literal substitution
+const MIN_XOR_LENGTH: usize = 3;
+const MAX_XOR_LENGTH: usize = 6;
+
+/// XOR constraints written in CNF among `clauses`: the `2^(n-1)` clauses over the same `n`
+/// variables ruling out every assignment with the wrong parity.
+pub(crate) fn find_xors(clauses: &[LiteralVec]) -> Vec<Xor> {
+    // Signs of the clauses over the same variables, sorted, as bits set for negative literals
+    let mut signs_by_vars: FnvHashMap<Vec<u32>, FnvHashSet<u32>> = FnvHashMap::default();
+
+    for lits in clauses.iter().filter(|lits| (MIN_XOR_LENGTH..=MAX_XOR_LENGTH).contains(&lits.len())) {
+        let mut sorted_lits = lits.clone();
+        sorted_lits.sort_unstable_by_key(|lit| lit.var());
+
+        let vars: Vec<u32> = sorted_lits.iter().map(|lit| lit.var()).collect();
+        if vars.windows(2).any(|pair| pair[0] == pair[1]) {
+            continue;
+        }
+
+        let signs = sorted_lits.iter().enumerate()
+            .filter(|(_, lit)| !lit.is_positive())
+            .fold(0, |signs, (position, _)| signs | 1 << position);
+        signs_by_vars.entry(vars).or_default().insert(signs);
+    }
+
+    let mut xors = Vec::new();
+
+    for (vars, signs) in signs_by_vars {
+        let num_clauses = 1 << (vars.len() - 1);
+        if signs.len() < num_clauses {
+            continue;
+        }
+
+        // A clause rules out the assignment falsifying it, whose true variables are its negative
+        // literals: the constraint has the other parity
+        for &parity in &[false, true] {
+            if signs.iter().filter(|signs| (signs.count_ones() % 2 == 1) != parity).count() == num_clauses {
+                xors.push(Xor::new(&vars, parity));
+            }
+        }
+    }
+
+    xors
+}
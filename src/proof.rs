@@ -0,0 +1,106 @@
+use model::Literal;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProofFormat {
+    Text,
+    Binary,
+}
+
+/**
+ * A DRAT proof, listing every clause the solver learns or deletes, in either the text or the
+ * binary format understood by DRAT checkers such as drat-trim.
+ *
+ * Write errors do not interrupt solving: the first one is kept and reported by `finish`.
+ *
+ * see https://www.cs.utexas.edu/~marijn/drat-trim/
+ */
+pub struct Proof {
+    writer: BufWriter<Box<dyn Write>>,
+    format: ProofFormat,
+    error: Option<io::Error>,
+}
+
+impl Proof {
+    pub fn new(writer: Box<dyn Write>, format: ProofFormat) -> Self {
+        Proof {
+            writer: BufWriter::new(writer),
+            format,
+            error: None,
+        }
+    }
+
+    pub fn create(filename: &str, format: ProofFormat) -> Result<Self, String> {
+        let file = File::create(filename)
+            .map_err(|_err| format!("Cannot create file: {}", filename))?;
+
+        Ok(Proof::new(Box::new(file), format))
+    }
+
+    pub fn add_clause(&mut self, lits: &[Literal]) {
+        self.write_step(None, lits);
+    }
+
+    pub fn delete_clause(&mut self, lits: &[Literal]) {
+        self.write_step(Some('d'), lits);
+    }
+
+    /// Flushes the proof, reporting the first error hit while writing it.
+    pub fn finish(mut self) -> Result<(), String> {
+        if self.error.is_none() {
+            self.error = self.writer.flush().err();
+        }
+
+        match self.error {
+            Some(err) => Err(format!("Failed to write proof: {}", err)),
+            None => Ok(()),
+        }
+    }
+
+    fn write_step(&mut self, step: Option<char>, lits: &[Literal]) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let result = match self.format {
+            ProofFormat::Text => self.write_text_step(step, lits),
+            ProofFormat::Binary => self.write_binary_step(step.unwrap_or('a'), lits),
+        };
+
+        self.error = result.err();
+    }
+
+    fn write_text_step(&mut self, step: Option<char>, lits: &[Literal]) -> io::Result<()> {
+        if let Some(step) = step {
+            write!(self.writer, "{} ", step)?;
+        }
+
+        for lit in lits {
+            write!(self.writer, "{} ", lit)?;
+        }
+
+        writeln!(self.writer, "0")
+    }
+
+    /// Every literal is mapped to `2 * var + sign` and written 7 bits at a time, least significant
+    /// first, with the high bit set on every byte but the last one.
+    fn write_binary_step(&mut self, step: char, lits: &[Literal]) -> io::Result<()> {
+        let mut bytes = vec![step as u8];
+
+        for lit in lits {
+            let mut encoded = 2 * lit.var() + if lit.is_positive() { 0 } else { 1 };
+
+            while encoded > 127 {
+                bytes.push((encoded & 127) as u8 | 128);
+                encoded >>= 7;
+            }
+            bytes.push(encoded as u8);
+        }
+        bytes.push(0);
+
+        self.writer.write_all(&bytes)
+    }
+}
@@ -0,0 +1,230 @@
+/// Decides when the solver abandons its current assignment and restarts from level 0. Learnt
+/// clauses and decider scores survive restarts.
+pub trait RestartStrategy {
+    /// Called after every conflict, with the LBD of the clause learnt from it.
+    fn on_conflict(&mut self, lbd: u32);
+
+    fn should_restart(&self) -> bool;
+
+    fn on_restart(&mut self);
+}
+
+/// Builds the restart strategy called `name`: one of `none`, `fixed`, `geometric`, `luby` or
+/// `glucose`.
+pub fn restart_strategy_from_name(name: &str) -> Result<Box<dyn RestartStrategy>, String> {
+    match name {
+        "none" => Ok(Box::new(NoRestarts)),
+        "fixed" => Ok(Box::new(FixedRestarts::new(100))),
+        "geometric" => Ok(Box::new(GeometricRestarts::new(100, 1.5))),
+        "luby" => Ok(Box::new(LubyRestarts::new(100))),
+        "glucose" => Ok(Box::new(GlucoseRestarts::new())),
+        _ => Err(format!("Unknown restart strategy: {}", name)),
+    }
+}
+
+pub struct NoRestarts;
+
+impl RestartStrategy for NoRestarts {
+    fn on_conflict(&mut self, _lbd: u32) {}
+
+    fn should_restart(&self) -> bool {
+        false
+    }
+
+    fn on_restart(&mut self) {}
+}
+
+/// Restarts every `interval` conflicts.
+pub struct FixedRestarts {
+    interval: u64,
+    conflicts: u64,
+}
+
+impl FixedRestarts {
+    pub fn new(interval: u64) -> Self {
+        FixedRestarts {
+            interval,
+            conflicts: 0,
+        }
+    }
+}
+
+impl RestartStrategy for FixedRestarts {
+    fn on_conflict(&mut self, _lbd: u32) {
+        self.conflicts += 1;
+    }
+
+    fn should_restart(&self) -> bool {
+        self.conflicts >= self.interval
+    }
+
+    fn on_restart(&mut self) {
+        self.conflicts = 0;
+    }
+}
+
+/// Restarts after `first` conflicts, the interval growing by `factor` after each restart.
+pub struct GeometricRestarts {
+    interval: f64,
+    factor: f64,
+    conflicts: u64,
+}
+
+impl GeometricRestarts {
+    pub fn new(first: u64, factor: f64) -> Self {
+        GeometricRestarts {
+            interval: first as f64,
+            factor,
+            conflicts: 0,
+        }
+    }
+}
+
+impl RestartStrategy for GeometricRestarts {
+    fn on_conflict(&mut self, _lbd: u32) {
+        self.conflicts += 1;
+    }
+
+    fn should_restart(&self) -> bool {
+        self.conflicts as f64 >= self.interval
+    }
+
+    fn on_restart(&mut self) {
+        self.conflicts = 0;
+        self.interval *= self.factor;
+    }
+}
+
+/**
+ * Restarts following the Luby sequence (1, 1, 2, 1, 1, 2, 4, 1, ...), scaled by `unit` conflicts.
+ *
+ * see https://doi.org/10.1016/0020-0190(93)90029-9
+ */
+pub struct LubyRestarts {
+    unit: u64,
+    restarts: u64,
+    conflicts: u64,
+}
+
+impl LubyRestarts {
+    pub fn new(unit: u64) -> Self {
+        LubyRestarts {
+            unit,
+            restarts: 0,
+            conflicts: 0,
+        }
+    }
+}
+
+impl RestartStrategy for LubyRestarts {
+    fn on_conflict(&mut self, _lbd: u32) {
+        self.conflicts += 1;
+    }
+
+    fn should_restart(&self) -> bool {
+        self.conflicts >= self.unit * luby(self.restarts)
+    }
+
+    fn on_restart(&mut self) {
+        self.conflicts = 0;
+        self.restarts += 1;
+    }
+}
+
+/// `index`-th element (from 0) of the Luby sequence.
+fn luby(mut index: u64) -> u64 {
+    // Find the finite subsequence containing index, and its size
+    let mut size = 1;
+    let mut sequence = 0;
+    while size < index + 1 {
+        sequence += 1;
+        size = 2 * size + 1;
+    }
+
+    while size - 1 != index {
+        size = (size - 1) >> 1;
+        sequence -= 1;
+        index %= size;
+    }
+
+    1 << sequence
+}
+
+/**
+ * Glucose-style dynamic restarts: the solver restarts when the recently learnt clauses are much
+ * worse, by LBD, than the ones learnt so far. Both are tracked as exponential moving averages, a
+ * fast one and a slow one.
+ *
+ * see https://www.ijcai.org/Proceedings/09/Papers/074.pdf
+ * see https://doi.org/10.1007/978-3-319-24318-4_12
+ */
+pub struct GlucoseRestarts {
+    fast_lbd: MovingAverage,
+    slow_lbd: MovingAverage,
+    margin: f64,
+    min_conflicts: u64,
+    conflicts: u64,
+}
+
+impl GlucoseRestarts {
+    pub fn new() -> Self {
+        GlucoseRestarts {
+            fast_lbd: MovingAverage::new(1.0 / 32.0),
+            slow_lbd: MovingAverage::new(1.0 / 4096.0),
+            margin: 1.25,
+            min_conflicts: 50,
+            conflicts: 0,
+        }
+    }
+}
+
+impl Default for GlucoseRestarts {
+    fn default() -> Self {
+        GlucoseRestarts::new()
+    }
+}
+
+impl RestartStrategy for GlucoseRestarts {
+    fn on_conflict(&mut self, lbd: u32) {
+        self.conflicts += 1;
+        self.fast_lbd.update(f64::from(lbd));
+        self.slow_lbd.update(f64::from(lbd));
+    }
+
+    fn should_restart(&self) -> bool {
+        self.conflicts >= self.min_conflicts && self.fast_lbd.value() > self.margin * self.slow_lbd.value()
+    }
+
+    fn on_restart(&mut self) {
+        self.conflicts = 0;
+    }
+}
+
+/// Exponential moving average. Until `1 / alpha` samples have been seen it is the plain average
+/// of the samples, so that its first value is not biased towards 0.
+struct MovingAverage {
+    alpha: f64,
+    samples: u64,
+    value: f64,
+}
+
+impl MovingAverage {
+    fn new(alpha: f64) -> Self {
+        MovingAverage {
+            alpha,
+            samples: 0,
+            value: 0.0,
+        }
+    }
+
+    fn update(&mut self, sample: f64) {
+        self.samples += 1;
+
+        let alpha = self.alpha.max(1.0 / self.samples as f64);
+        self.value += alpha * (sample - self.value);
+    }
+
+    fn value(&self) -> f64 {
+        self.value
+    }
+}
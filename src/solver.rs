@@ -1,273 +1,1815 @@
 use model::Clause;
 use model::LiteralSet;
-use model::ClauseVec;
-use model::Decision;
+use model::ClauseDb;
+use model::Trail;
 use solver::Constant::Sat;
 use solver::Constant::Unsat;
 use solver::Constant::Conflict;
 use solver::Constant::NoConflict;
 use model::Literal;
+use decider::Decider;
 use decider::VSIDSDecider;
-use model::ClauseId;
+use model::ClauseRef;
+use model::Reason;
+use model::Xor;
+use gauss::GaussMatrix;
 use fnv::FnvHashMap;
 use fnv::FnvHashSet;
 use conflict_analyzer::learn_from_conflict;
+use conflict_analyzer::explain_failed_assumption;
+use std::iter;
+use std::mem;
 use std::prelude::v1::Vec;
-use model::clause_vec_to_string;
+use std::time::Instant;
+use proof::Proof;
+use restart::RestartStrategy;
+use restart::NoRestarts;
+use polarity::Phases;
+use polarity::Polarity;
+use preprocessing::eliminate_vars;
+use preprocessing::EliminationStack;
+use preprocessing::find_equivalences;
+use preprocessing::find_xors;
+use preprocessing::substitute_equivalences;
+use preprocessing::OccurrenceLists;
+use preprocessing::subsume_clauses;
+use model::LiteralVec;
+
+const CLAUSE_ACTIVITY_DECAY: f64 = 0.999;
+const CLAUSE_ACTIVITY_LIMIT: f64 = 1e20;
+// Learnt clauses are reduced after 2000 conflicts, then 2300 more, then 2600 more...
+const FIRST_REDUCTION_INTERVAL: u64 = 2000;
+const REDUCTION_INTERVAL_INCREMENT: u64 = 300;
+// Learnt clauses with an LBD up to this one are never deleted
+const GLUE_LBD: u32 = 2;
+// The clause database is compacted once deleted clauses take this fraction of it
+const MAX_GARBAGE_FRACTION: f64 = 0.2;
+// Learnt clauses are checked for subsumption at the first restart every this many conflicts
+const SUBSUMPTION_INTERVAL: u64 = 5000;
+// Literals are probed at the first restart every this many conflicts, for a tenth of the
+// propagations made so far, or at least this many
+const PROBING_INTERVAL: u64 = 10000;
+const PROBING_EFFORT: u64 = 10;
+const MIN_PROBING_PROPAGATIONS: u64 = 100_000;
+// Clauses are vivified at the first restart every this many conflicts, for at most a tenth of the
+// time spent since the last time
+const VIVIFICATION_INTERVAL: u64 = 10000;
+const VIVIFICATION_EFFORT: u32 = 10;
 
 #[derive(Debug, PartialEq)]
-pub enum Constant {
+pub(crate) enum Constant {
     Sat,
     Unsat,
     Conflict,
     NoConflict,
 }
 
+/// Counters describing the search done by a `Solver` across all its calls to `solve`.
+#[derive(Debug, Default, Clone)]
+pub struct Statistics {
+    pub decisions: u64,
+    pub propagations: u64,
+    pub conflicts: u64,
+    pub learnt_clauses: u64,
+    pub deleted_clauses: u64,
+    pub restarts: u64,
+    pub chrono_backtracks: u64,
+    pub eliminated_vars: u64,
+    pub subsumed_clauses: u64,
+    pub strengthened_clauses: u64,
+    pub failed_literals: u64,
+    pub implied_literals: u64,
+    pub substituted_vars: u64,
+    pub vivified_clauses: u64,
+    pub detected_xors: u64,
+}
+
+/// Entry of the watch list of a literal: a clause watched by it, and another literal of the clause,
+/// whose being true means the clause needs not be visited.
+#[derive(Debug, Clone, Copy)]
+struct Watch {
+    clause_ref: ClauseRef,
+    blocker: Literal,
+}
+
+/// A CDCL SAT solver.
+///
+/// Variables are numbered from 1, the same way DIMACS does, and literals are plain signed
+/// integers:
+///
+/// ```
+/// use cnsat::Solver;
+///
+/// let mut solver = Solver::new();
+/// solver.add_clause_from_lits(&[1, 2]);
+/// solver.add_clause_from_lits(&[-1]);
+///
+/// assert!(solver.solve().is_some());
+/// assert_eq!(solver.value(2), Some(true));
+/// ```
 pub struct Solver {
-    clauses: ClauseVec,
-    learnt_clauses: FnvHashSet<ClauseId>,
-    lit_to_clause: FnvHashMap<Literal, Vec<ClauseId>>,
-    watched_lit_to_clause: FnvHashMap<Literal, FnvHashSet<ClauseId>>,
-    satisfied_clauses: FnvHashSet<ClauseId>,
-    assigned_lits: LiteralSet,
-    decision_stack: Vec<Decision>,
-    decider: VSIDSDecider,
+    clauses: ClauseDb,
+    // Clauses watched by every literal, indexed by literal
+    watches: Vec<Vec<Watch>>,
+    // Other literal of every binary clause, indexed by literal: the clause implies it once the
+    // indexed literal is false. Binary clauses are not part of the clause database.
+    binary_watches: Vec<Vec<Literal>>,
+    unit_clauses: Vec<Reason>,
+    trail: Trail,
+    // Literals of the trail from this position on have not been propagated yet
+    propagated: usize,
+    assumptions: Vec<Literal>,
+    failed_assumptions: LiteralSet,
+    decider: Box<dyn Decider>,
+    seed: Option<u64>,
+    random_decision_fraction: f64,
+    phases: Phases,
+    restart_strategy: Box<dyn RestartStrategy>,
+    chrono_threshold: Option<u32>,
+    elimination: bool,
+    subsumption: bool,
+    next_subsumption: u64,
+    probing: bool,
+    next_probing: u64,
+    // Variables are probed in turn, across calls
+    next_probed_var: u32,
+    // Assignments above level 0 are tentative ones of probing or vivification, hidden from the
+    // decider and from the saved phases, which only account for the search
+    silent_assignments: bool,
+    vivification: bool,
+    original_vivification: bool,
+    next_vivification: u64,
+    last_vivification: Instant,
+    gaussian_elimination: bool,
+    xors: Vec<Xor>,
+    gauss_matrix: Option<GaussMatrix>,
+    // Position of the trail up to which the XOR constraints were propagated
+    xor_propagated: usize,
+    // Clauses explaining the deductions of the XOR constraints, kept while they are reasons
+    xor_reasons: Vec<ClauseRef>,
+    // Clauses were added since the formula was last simplified
+    needs_preprocessing: bool,
+    eliminated_vars: FnvHashSet<u32>,
+    elimination_stack: EliminationStack,
+    clause_activity_increment: f64,
+    reduction_interval: u64,
+    next_reduction: u64,
+    num_vars: u32,
+    inconsistent: bool,
+    model: Option<LiteralSet>,
+    proof: Option<Proof>,
+    statistics: Statistics,
     verbose: bool,
 }
 
+impl Default for Solver {
+    fn default() -> Self {
+        Solver::new()
+    }
+}
+
 impl Solver {
     pub fn new() -> Self {
         Solver {
-            clauses: ClauseVec::new(),
-            learnt_clauses: FnvHashSet::default(),
-            lit_to_clause: FnvHashMap::default(),
-            watched_lit_to_clause: FnvHashMap::default(),
-            satisfied_clauses: FnvHashSet::default(),
-            assigned_lits: LiteralSet::default(),
-            decision_stack: vec![Decision::from(Literal::non_existent(), 0)],
-            decider: VSIDSDecider::new(),
+            clauses: ClauseDb::new(),
+            watches: Vec::new(),
+            binary_watches: Vec::new(),
+            unit_clauses: Vec::new(),
+            trail: Trail::new(),
+            propagated: 0,
+            assumptions: Vec::new(),
+            failed_assumptions: LiteralSet::default(),
+            decider: Box::new(VSIDSDecider::new()),
+            seed: None,
+            random_decision_fraction: 0.0,
+            phases: Phases::new(Polarity::Saved),
+            restart_strategy: Box::new(NoRestarts),
+            chrono_threshold: None,
+            elimination: true,
+            subsumption: true,
+            next_subsumption: SUBSUMPTION_INTERVAL,
+            probing: true,
+            next_probing: PROBING_INTERVAL,
+            next_probed_var: 0,
+            silent_assignments: false,
+            vivification: true,
+            original_vivification: false,
+            next_vivification: VIVIFICATION_INTERVAL,
+            last_vivification: Instant::now(),
+            gaussian_elimination: true,
+            xors: Vec::new(),
+            gauss_matrix: None,
+            xor_propagated: 0,
+            xor_reasons: Vec::new(),
+            needs_preprocessing: false,
+            eliminated_vars: FnvHashSet::default(),
+            elimination_stack: EliminationStack::new(),
+            clause_activity_increment: 1.0,
+            reduction_interval: FIRST_REDUCTION_INTERVAL,
+            next_reduction: FIRST_REDUCTION_INTERVAL,
+            num_vars: 0,
+            inconsistent: false,
+            model: None,
+            proof: None,
+            statistics: Statistics::default(),
             verbose: false,
         }
     }
 
-    pub fn add_clause(&mut self, clause: Clause) -> usize {
-        let clause_id = self.clauses.len();
+    /// Creates a fresh variable and returns its positive literal.
+    pub fn new_var(&mut self) -> Literal {
+        self.num_vars += 1;
 
-        clause.lits().iter().cloned().for_each(|lit| {
-            self.lit_to_clause.entry(lit)
-                .and_modify(|clauses| clauses.push(clause_id))
-                .or_insert_with(|| vec![clause_id]);
-        });
+        Literal::from_var(self.num_vars, true)
+    }
+
+    pub fn num_vars(&self) -> u32 {
+        self.num_vars
+    }
+
+    /// Logs every clause learnt or deleted from now on into `proof`, including the empty clause
+    /// once the formula is found UNSAT.
+    pub fn set_proof(&mut self, proof: Proof) {
+        self.proof = Some(proof);
+    }
+
+    pub fn take_proof(&mut self) -> Option<Proof> {
+        self.proof.take()
+    }
+
+    /// Replaces the restart strategy, no restarts by default.
+    pub fn set_restart_strategy(&mut self, restart_strategy: Box<dyn RestartStrategy>) {
+        self.restart_strategy = restart_strategy;
+    }
+
+    /// Replaces the decider picking the variables to decide, VSIDS by default. The scores of the
+    /// previous one are lost.
+    pub fn set_decider(&mut self, mut decider: Box<dyn Decider>) {
+        if let Some(seed) = self.seed {
+            decider.set_seed(seed);
+        }
+        decider.set_random_decision_fraction(self.random_decision_fraction);
+
+        for clause_ref in self.clauses.iter() {
+            self.clauses.lits(clause_ref).iter().for_each(|lit| decider.add_var(lit.var()));
+        }
+        self.binary_watches.iter().enumerate()
+            .filter(|(_, implied_lits)| !implied_lits.is_empty())
+            .for_each(|(index, _)| decider.add_var(index as u32 >> 1));
+        self.xors.iter().flat_map(|xor| xor.vars()).for_each(|&var| decider.add_var(var));
+        self.trail.lits().iter().for_each(|&lit| decider.assign_lit(lit));
+        // Eliminated variables are kept out of decisions by leaving them assigned
+        self.eliminated_vars.iter().for_each(|&var| decider.assign_lit(Literal::from_var(var, true)));
+
+        self.decider = decider;
+    }
+
+    /// Seeds the random choices of the solver: ties between variables of equal scores, random
+    /// decisions and random polarities. Runs with the same seed and options take the same
+    /// decisions. Ties go to the lowest variable when no seed is given.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+        self.decider.set_seed(seed);
+        self.phases.set_seed(seed);
+    }
+
+    /// Decides a variable picked at random, instead of the best scored one, for the given fraction
+    /// of the decisions, 0 by default.
+    pub fn set_random_decision_fraction(&mut self, fraction: f64) {
+        self.random_decision_fraction = fraction;
+        self.decider.set_random_decision_fraction(fraction);
+    }
+
+    /// Replaces the polarity decided variables are assigned, phase saving by default. It can be
+    /// changed between calls to `solve`.
+    pub fn set_polarity(&mut self, polarity: Polarity) {
+        self.phases.set_polarity(polarity);
+    }
+
+    /// Enables chronological backtracking: after a conflict, the solver only undoes the conflict
+    /// level when backjumping would undo more than `threshold` levels. Literals keep the level of
+    /// their implying clause, even when it is lower than the current one. Disabled by default.
+    ///
+    /// see https://doi.org/10.1007/978-3-319-94144-8_7
+    pub fn set_chrono_threshold(&mut self, threshold: Option<u32>) {
+        self.chrono_threshold = threshold;
+    }
+
+    /// Enables bounded variable elimination before the search, whenever clauses were added since
+    /// the last call to `solve`. Eliminated variables come back as soon as a clause or an
+    /// assumption refers to them. Enabled by default.
+    pub fn set_elimination(&mut self, enabled: bool) {
+        self.elimination = enabled;
+    }
+
+    /// Enables subsumption and self-subsuming resolution, on every clause before the search like
+    /// variable elimination, and on the learnt clauses every few thousand conflicts during the
+    /// search. Enabled by default.
+    pub fn set_subsumption(&mut self, enabled: bool) {
+        self.subsumption = enabled;
+    }
+
+    /// Enables failed-literal probing, before the search and every few thousand conflicts during
+    /// it, along with the substitution of equivalent literals before the search. Enabled by
+    /// default.
+    pub fn set_probing(&mut self, enabled: bool) {
+        self.probing = enabled;
+    }
+
+    /// Enables the vivification of learnt clauses every few thousand conflicts. Enabled by default.
+    pub fn set_vivification(&mut self, enabled: bool) {
+        self.vivification = enabled;
+    }
+
+    /// Vivifies the original clauses as well as the learnt ones. Disabled by default.
+    pub fn set_original_vivification(&mut self, enabled: bool) {
+        self.original_vivification = enabled;
+    }
+
+    /// Enables Gauss-Jordan elimination on the XOR constraints, those added and those found among
+    /// the clauses. Enabled by default, but not used when a proof is written, since it cannot
+    /// justify its deductions.
+    pub fn set_gaussian_elimination(&mut self, enabled: bool) {
+        self.gaussian_elimination = enabled;
+    }
+
+    pub fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
+    /// Adds the clause made of the given DIMACS literals, e.g. `&[1, -3]` for `x1 ∨ ¬x3`.
+    /// Variables that were not created yet are created on the fly.
+    pub fn add_clause_from_lits(&mut self, lits: &[i32]) {
+        let clause_lits: Vec<Literal> = lits.iter().map(|&lit| Literal::from(lit)).collect();
 
-        self.add_watched_lit(clause_id, clause.first_watched_lit());
-        if let Some(lit) = clause.second_watched_lit() {
-            self.add_watched_lit(clause_id, lit);
+        self.add_clause(Clause::from_lits(&clause_lits));
+    }
+
+    /// Adds the XOR constraint of the given DIMACS literals as CryptoMiniSat reads them, e.g.
+    /// `&[1, -3]` for `x1 ⊕ ¬x3`. Variables that were not created yet are created on the fly.
+    pub fn add_xor_from_lits(&mut self, lits: &[i32]) {
+        let xor_lits: Vec<Literal> = lits.iter().map(|&lit| Literal::from(lit)).collect();
+
+        self.add_xor(Xor::from_lits(&xor_lits));
+    }
+
+    /// Adds an XOR constraint to the formula, handled by Gauss-Jordan elimination. When it is
+    /// disabled, or when a proof is written, the constraint is added as clauses instead: the proof
+    /// must be set first. Long constraints then get fresh variables, created as by `new_var`.
+    pub fn add_xor(&mut self, xor: Xor) {
+        xor.vars().iter().for_each(|&var| self.num_vars = self.num_vars.max(var));
+
+        if !self.gaussian_elimination || self.proof.is_some() {
+            xor.clauses(&mut self.num_vars).iter().for_each(|lits| self.add_clause(Clause::from_lits(lits)));
+            return;
         }
 
-        self.decider.add_clause(&clause);
-        self.clauses.push(clause);
+        xor.vars().iter().for_each(|&var| self.restore_var(var));
+        self.backtrack_to_level(0);
+        self.needs_preprocessing = true;
 
-        clause_id
+        xor.vars().iter().for_each(|&var| self.decider.add_var(var));
+        self.xors.push(xor);
+    }
+
+    /// Adds a clause to the formula. Clauses can be added between calls to `solve`, in which case
+    /// the solver first goes back to level 0.
+    pub fn add_clause(&mut self, mut clause: Clause) {
+        clause.lits().iter().for_each(|lit| self.num_vars = self.num_vars.max(lit.var()));
+        clause.lits().iter().for_each(|lit| self.restore_var(lit.var()));
+        self.backtrack_to_level(0);
+        self.needs_preprocessing = true;
+        self.phases.add_clause(clause.lits());
+
+        // Clauses satisfied at level 0 stay satisfied forever
+        let is_satisfied = clause.lits().iter().any(|&lit| self.trail.is_true(lit));
+
+        if clause.is_empty() {
+            self.mark_inconsistent();
+        } else if !clause.is_tautology() && !is_satisfied {
+            // Literals falsified at level 0 stay that way, so they must not be watched
+            sort_watched_lits(&self.trail, clause.lits_mut());
+            if self.trail.is_false(clause.lits()[0]) {
+                self.mark_inconsistent();
+                return;
+            }
+
+            self.decider.add_clause(&clause);
+
+            let is_unit = clause.lits().get(1).is_none_or(|&lit| self.trail.is_false(lit));
+            let reason = if clause.lits().len() == 2 {
+                self.attach_binary_clause(&clause)
+            } else {
+                Reason::Clause(self.attach_clause(&clause, false))
+            };
+
+            if is_unit {
+                self.unit_clauses.push(reason);
+            }
+        }
+    }
+
+    /// Adds a clause of two literals to the implication lists of both.
+    fn attach_binary_clause(&mut self, clause: &Clause) -> Reason {
+        let lits = [clause.lits()[0], clause.lits()[1]];
+
+        lits.iter().for_each(|lit| self.decider.add_var(lit.var()));
+        self.watch_binary(lits[0], lits[1]);
+        self.watch_binary(lits[1], lits[0]);
+
+        Reason::Binary(lits)
     }
 
     #[inline]
-    fn add_watched_lit(&mut self, clause_id: ClauseId, lit: Literal) {
-        self.watched_lit_to_clause.entry(lit)
-            .and_modify(|clauses| { clauses.insert(clause_id); })
-            .or_insert_with(|| {
-                let mut set = FnvHashSet::default();
-                set.insert(clause_id);
-                set
-            });
+    fn watch_binary(&mut self, lit: Literal, other_lit: Literal) {
+        if lit.index() >= self.binary_watches.len() {
+            self.binary_watches.resize(lit.index() + 2, Vec::new());
+        }
+
+        self.binary_watches[lit.index()].push(other_lit);
     }
 
+    fn attach_clause(&mut self, clause: &Clause, learnt: bool) -> ClauseRef {
+        clause.lits().iter().for_each(|lit| self.decider.add_var(lit.var()));
+        let clause_ref = self.clauses.add(clause, learnt);
+        self.watch_clause(clause_ref);
+
+        clause_ref
+    }
+
+    /// Adds the clause to the watch lists of its first two literals. Unit clauses are not watched.
+    fn watch_clause(&mut self, clause_ref: ClauseRef) {
+        let lits = self.clauses.lits(clause_ref);
+        if lits.len() < 2 {
+            return;
+        }
+
+        let (lit_a, lit_b) = (lits[0], lits[1]);
+        self.watch_lit(lit_a, Watch { clause_ref, blocker: lit_b });
+        self.watch_lit(lit_b, Watch { clause_ref, blocker: lit_a });
+    }
+
+    #[inline]
+    fn watch_lit(&mut self, lit: Literal, watch: Watch) {
+        if lit.index() >= self.watches.len() {
+            self.watches.resize(lit.index() + 2, Vec::new());
+        }
+
+        self.watches[lit.index()].push(watch);
+    }
+
+    fn unwatch_lit(&mut self, lit: Literal, clause_ref: ClauseRef) {
+        if let Some(watches) = self.watches.get_mut(lit.index()) {
+            watches.retain(|watch| watch.clause_ref != clause_ref);
+        }
+    }
+
+    /// Solves the formula, returning a model if it is satisfiable. Every variable is part of the
+    /// model, including those that do not occur in any clause.
     pub fn solve(&mut self) -> Option<LiteralSet> {
-        match self.dpll() {
-            Sat => Some(self.assigned_lits.clone()),
+        self.solve_with_assumptions(&[])
+    }
+
+    /// Solves the formula under the given assumptions, which are decided, in order, before any
+    /// other literal. Assumptions only hold for this call: learnt clauses are kept for the next
+    /// ones, and more clauses can be added in between.
+    pub fn solve_with_assumptions(&mut self, assumptions: &[Literal]) -> Option<LiteralSet> {
+        assumptions.iter().for_each(|lit| self.num_vars = self.num_vars.max(lit.var()));
+        assumptions.iter().for_each(|lit| self.restore_var(lit.var()));
+        self.backtrack_to_level(0);
+        self.assumptions = assumptions.to_vec();
+        self.failed_assumptions.clear();
+
+        self.model = match self.dpll() {
+            Sat => {
+                // Unassigned variables are false, until eliminated ones get the value their
+                // clauses need
+                let mut values: Vec<bool> = (0..=self.num_vars)
+                    .map(|var| self.trail.is_true(Literal::from_var(var, true)))
+                    .collect();
+                self.elimination_stack.extend_model(&mut values);
+
+                Some((1..=self.num_vars).map(|var| Literal::from_var(var, values[var as usize])).collect())
+            },
             Unsat => None,
             other => panic!("DPLL must return either SAT or UNSAT. got: {:?}", other),
-        }
+        };
+
+        self.model.clone()
+    }
+
+    /// Value of the DIMACS literal `lit` in the model found by the last call to `solve`, or `None`
+    /// if there is no such model.
+    pub fn value(&self, lit: i32) -> Option<bool> {
+        let lit = Literal::from(lit);
+
+        self.model.as_ref().map(|model| model.contains(&lit))
+    }
+
+    /// Subset of the assumptions given to the last call to `solve_with_assumptions` that made it
+    /// UNSAT. It is empty when the formula is UNSAT no matter the assumptions.
+    pub fn failed_assumptions(&self) -> &LiteralSet {
+        &self.failed_assumptions
     }
 
     fn dpll(&mut self) -> Constant {
+        if self.inconsistent || self.propagate_unit_clauses() == Conflict || self.preprocess() == Conflict {
+            self.mark_inconsistent();
+            return Unsat;
+        }
+
         loop {
-            let lit = self.decide_next_literal();
+            let lit = match self.next_assumption() {
+                Some(assumption) if self.trail.is_false(assumption) => {
+                    self.failed_assumptions = explain_failed_assumption(assumption, &self.trail, &self.clauses);
+                    return Unsat;
+                },
+                Some(assumption) => match self.assume(assumption) {
+                    Some(lit) => lit,
+                    None => continue,
+                },
+                None => match self.decide_next_literal() {
+                    Some(lit) => lit,
+                    None => return Sat,
+                },
+            };
 
-            if lit.is_none() {
-                return Sat;
-            }
+            let decision_level = self.current_decision_level();
+            self.assign(lit, decision_level, None);
 
-            let mut lit = lit.unwrap();
-            while self.deduce(lit) == Conflict {
-                let learnt_clause_id = self.analyze_conflict();
+            while let Some(conflict) = self.deduce() {
+                self.statistics.conflicts += 1;
 
+                if !self.resolve_conflict(conflict) {
+                    self.mark_inconsistent();
+                    return Unsat;
+                }
+            }
 
-                if !self.backtrack(learnt_clause_id) {
+            if self.restart_strategy.should_restart() {
+                self.restart();
+
+                if self.statistics.conflicts >= self.next_subsumption && self.subsume_learnt_clauses() == Conflict {
+                    self.mark_inconsistent();
+                    return Unsat;
+                }
+                if self.statistics.conflicts >= self.next_probing && self.probe_literals() == Conflict {
+                    self.mark_inconsistent();
+                    return Unsat;
+                }
+                if self.statistics.conflicts >= self.next_vivification && self.vivify_clauses() == Conflict {
+                    self.mark_inconsistent();
                     return Unsat;
                 }
+            }
+
+            if self.statistics.conflicts >= self.next_reduction {
+                self.reduce_learnt_clauses();
+            }
+            // Explanations of the XOR constraints leave garbage behind between reductions too
+            if self.clauses.garbage_fraction() > MAX_GARBAGE_FRACTION {
+                self.collect_garbage();
+            }
+        }
+    }
+
+    /// The formula is UNSAT no matter the assumptions.
+    fn mark_inconsistent(&mut self) {
+        if !self.inconsistent {
+            if let Some(proof) = self.proof.as_mut() {
+                proof.add_clause(&[]);
+            }
+        }
+
+        self.inconsistent = true;
+    }
+
+    /// Propagates the unit clauses at level 0, before any decision is taken.
+    fn propagate_unit_clauses(&mut self) -> Constant {
+        let unit_clauses: Vec<Reason> = self.unit_clauses.drain(..).collect();
+
+        for reason in unit_clauses {
+            let lit = reason.lits(&self.clauses)[0];
+
+            if self.trail.is_false(lit) {
+                return Conflict;
+            }
+
+            if !self.trail.is_true(lit) {
+                self.assign(lit, 0, Some(reason));
+            }
+        }
+
+        match self.deduce() {
+            Some(_) => Conflict,
+            None => NoConflict,
+        }
+    }
+
+    /// Simplifies the formula at level 0, once the unit clauses are propagated, if clauses were
+    /// added since the last time.
+    fn preprocess(&mut self) -> Constant {
+        if !self.needs_preprocessing {
+            return NoConflict;
+        }
+
+        if self.gaussian_elimination && self.proof.is_none() {
+            self.detect_xors();
+        }
+        if !self.xors.is_empty() {
+            match GaussMatrix::new(&self.xors) {
+                Some(gauss_matrix) => self.gauss_matrix = Some(gauss_matrix),
+                None => return Conflict,
+            }
+            self.xor_propagated = 0;
+        }
+
+        if self.probe_literals() == Conflict {
+            return Conflict;
+        }
+        if self.probing || self.subsumption || self.elimination {
+            self.simplify_irredundant_clauses();
+        }
+        self.needs_preprocessing = false;
+
+        if self.inconsistent {
+            return Conflict;
+        }
+        self.propagate_unit_clauses()
+    }
+
+    /// Substitutes equivalent literals, then runs subsumption and bounded variable elimination on
+    /// the irredundant clauses, the original and binary ones, then rebuilds the clause database
+    /// out of the remaining ones and of the learnt clauses left without removed variables. The
+    /// assumptions are not removed.
+    fn simplify_irredundant_clauses(&mut self) {
+        if let Some(proof) = self.proof.as_mut() {
+            // The clauses implying the literals of level 0 are about to be deleted
+            self.trail.lits().iter().for_each(|&lit| proof.add_clause(&[lit]));
+        }
+
+        let mut irredundant_clauses = Vec::new();
+        let mut learnt_clauses = Vec::new();
+
+        for clause_ref in self.clauses.iter().collect::<Vec<ClauseRef>>() {
+            let lits = self.clauses.lits(clause_ref).to_vec();
+
+            if !self.clauses.is_learnt(clause_ref) {
+                irredundant_clauses.extend(self.root_simplified(&lits));
+            } else if !lits.iter().any(|&lit| self.trail.is_true(lit)) {
+                learnt_clauses.push(Clause::new_asserting_clause(lits, self.clauses.lbd(clause_ref)));
+            } else if let Some(proof) = self.proof.as_mut() {
+                proof.delete_clause(&lits);
+            }
+        }
+
+        for lits in self.binary_clauses() {
+            irredundant_clauses.extend(self.root_simplified(&lits));
+        }
+
+        // Variables of the XOR constraints stay, the Gauss-Jordan elimination holds them
+        let frozen_vars = self.assumptions.iter().map(|lit| lit.var())
+            .chain(self.xors.iter().flat_map(|xor| xor.vars().to_vec()))
+            .collect();
+        let mut substituted_vars = Vec::new();
+
+        if self.probing {
+            match find_equivalences(&irredundant_clauses, &frozen_vars) {
+                Ok(substitutions) => {
+                    irredundant_clauses = substitute_equivalences(irredundant_clauses, &substitutions,
+                                                                  &mut self.elimination_stack, self.proof.as_mut());
+                    substituted_vars.extend(substitutions.keys());
+                },
+                Err(lit) => {
+                    // Both the literal and its complementary are implied
+                    if let Some(proof) = self.proof.as_mut() {
+                        proof.add_clause(&[lit]);
+                    }
+                    self.mark_inconsistent();
+                    return;
+                },
+            }
+        }
+
+        let mut occurrence_lists = OccurrenceLists::new(irredundant_clauses);
+
+        if self.subsumption {
+            let (subsumed_clauses, strengthened_clauses) = subsume_clauses(&mut occurrence_lists, |_| true, self.proof.as_mut());
+            self.statistics.subsumed_clauses += subsumed_clauses as u64;
+            self.statistics.strengthened_clauses += strengthened_clauses as u64;
+        }
+
+        let eliminated_vars = if self.elimination {
+            eliminate_vars(&mut occurrence_lists, &frozen_vars, &mut self.elimination_stack, self.proof.as_mut())
+        } else {
+            Vec::new()
+        };
+
+        self.statistics.substituted_vars += substituted_vars.len() as u64;
+        self.statistics.eliminated_vars += eliminated_vars.len() as u64;
+        for var in substituted_vars.into_iter().chain(eliminated_vars) {
+            self.eliminated_vars.insert(var);
+            // Removed variables are kept out of decisions by leaving them assigned
+            self.decider.assign_lit(Literal::from_var(var, true));
+        }
+
+        self.clauses = ClauseDb::new();
+        self.watches.clear();
+        self.binary_watches.clear();
+        self.trail.clear_reasons();
+        self.xor_reasons.clear();
+        self.phases.clear_occurrences();
+
+        for lits in occurrence_lists.into_clauses() {
+            self.add_clause(Clause::from_lits(&lits));
+        }
+
+        for mut clause in learnt_clauses {
+            if clause.lits().iter().any(|lit| self.eliminated_vars.contains(&lit.var())) {
+                if let Some(proof) = self.proof.as_mut() {
+                    proof.delete_clause(clause.lits());
+                }
+                continue;
+            }
+
+            sort_watched_lits(&self.trail, clause.lits_mut());
+            self.attach_clause(&clause, true);
+        }
+    }
+
+    /// Deletes the learnt clauses subsumed by other clauses, and strengthens the ones other clauses
+    /// self-subsume, which are replaced by shorter learnt clauses. Clauses with literals assigned
+    /// at level 0 are left out. Must be called at level 0.
+    fn subsume_learnt_clauses(&mut self) -> Constant {
+        self.next_subsumption = self.statistics.conflicts + SUBSUMPTION_INTERVAL;
+
+        if !self.subsumption {
+            return NoConflict;
+        }
+        if self.deduce().is_some() {
+            return Conflict;
+        }
+
+        // Learnt clauses of the clause database, by position in the occurrence lists
+        let mut learnt_clause_refs = Vec::new();
+        let mut clauses = Vec::new();
+        let trail = &self.trail;
+
+        for clause_ref in self.clauses.iter() {
+            let lits = self.clauses.lits(clause_ref);
+
+            if lits.iter().all(|&lit| trail.value(lit).is_none()) {
+                learnt_clause_refs.push(Some(clause_ref).filter(|&clause_ref| self.clauses.is_learnt(clause_ref)));
+                clauses.push(lits.to_vec());
+            }
+        }
+        for lits in self.binary_clauses() {
+            if lits.iter().all(|&lit| trail.value(lit).is_none()) {
+                learnt_clause_refs.push(None);
+                clauses.push(lits.to_vec());
+            }
+        }
+
+        let mut occurrence_lists = OccurrenceLists::new(clauses);
+        let (subsumed_clauses, strengthened_clauses) = subsume_clauses(&mut occurrence_lists,
+            |position| learnt_clause_refs[position].is_some(), self.proof.as_mut());
+        self.statistics.subsumed_clauses += subsumed_clauses as u64;
+        self.statistics.strengthened_clauses += strengthened_clauses as u64;
+
+        let mut deleted_clause_refs = FnvHashSet::default();
+
+        for (position, clause_ref) in learnt_clause_refs.into_iter().enumerate() {
+            let clause_ref = match clause_ref {
+                Some(clause_ref) => clause_ref,
+                None => continue,
+            };
+
+            if occurrence_lists.is_removed(position) {
+                deleted_clause_refs.insert(clause_ref);
+                continue;
+            }
+
+            let lits = occurrence_lists.clause(position);
+            if lits.len() == self.clauses.len(clause_ref) {
+                continue;
+            }
+
+            deleted_clause_refs.insert(clause_ref);
+
+            match lits.len() {
+                0 => return Conflict,
+                1 => match self.trail.value(lits[0]) {
+                    Some(false) => return Conflict,
+                    Some(true) => {},
+                    None => self.assign(lits[0], 0, None),
+                },
+                2 => { self.attach_binary_clause(&Clause::from_lits(lits)); },
+                _ => {
+                    let lbd = self.clauses.lbd(clause_ref).min(lits.len() as u32);
+                    self.attach_clause(&Clause::new_asserting_clause(lits.to_vec(), lbd), true);
+                },
+            }
+        }
+
+        // The proof already has the deletions, in the order they happened
+        self.detach_clauses(&deleted_clause_refs);
+
+        match self.deduce() {
+            Some(_) => Conflict,
+            None => NoConflict,
+        }
+    }
+
+    /// Adds the XOR constraints written as clauses among the original ones, which are kept as well.
+    fn detect_xors(&mut self) {
+        let clauses = &self.clauses;
+        let original_clauses: Vec<LiteralVec> = clauses.iter()
+            .filter(|&clause_ref| !clauses.is_learnt(clause_ref))
+            .map(|clause_ref| clauses.lits(clause_ref).to_vec())
+            .collect();
+
+        let known_xors: FnvHashSet<Xor> = self.xors.iter().cloned().collect();
+        for xor in find_xors(&original_clauses) {
+            if !known_xors.contains(&xor) {
+                self.statistics.detected_xors += 1;
+                self.xors.push(xor);
+            }
+        }
+    }
+
+    /// Failed-literal probing: both literals of every variable are assigned in turn at level 1 and
+    /// propagated. When one of them leads to a conflict, its complementary is implied at level 0,
+    /// and so are the literals both of them imply. Variables are probed round-robin until the
+    /// propagations exceed the budget. Must be called at level 0.
+    fn probe_literals(&mut self) -> Constant {
+        self.next_probing = self.statistics.conflicts + PROBING_INTERVAL;
+
+        if !self.probing {
+            return NoConflict;
+        }
+        if self.deduce().is_some() {
+            return Conflict;
+        }
+
+        let budget = MIN_PROBING_PROPAGATIONS.max(self.statistics.propagations / PROBING_EFFORT);
+        let max_propagations = self.statistics.propagations + budget;
+
+        for _ in 0..self.num_vars {
+            if self.statistics.propagations > max_propagations {
+                break;
+            }
+
+            self.next_probed_var = self.next_probed_var % self.num_vars + 1;
+            let lit = Literal::from_var(self.next_probed_var, true);
+            if self.eliminated_vars.contains(&lit.var()) || self.trail.value(lit).is_some() {
+                continue;
+            }
+
+            let positive_implied_lits = match self.probe(lit) {
+                Some(implied_lits) => implied_lits,
+                None => {
+                    self.statistics.failed_literals += 1;
+                    self.assign_root_lit(lit.complementary());
+                    if self.deduce().is_some() {
+                        return Conflict;
+                    }
+                    continue;
+                },
+            };
+
+            let negative_implied_lits = match self.probe(lit.complementary()) {
+                Some(implied_lits) => implied_lits,
+                None => {
+                    self.statistics.failed_literals += 1;
+                    self.assign_root_lit(lit);
+                    if self.deduce().is_some() {
+                        return Conflict;
+                    }
+                    continue;
+                },
+            };
+
+            let negative_implied_lits: LiteralSet = negative_implied_lits.into_iter().collect();
+            for implied_lit in positive_implied_lits.into_iter().filter(|lit| negative_implied_lits.contains(lit)) {
+                if let Some(proof) = self.proof.as_mut() {
+                    proof.add_clause(&[lit.complementary(), implied_lit]);
+                    proof.add_clause(&[lit, implied_lit]);
+                }
+                self.statistics.implied_literals += 1;
+                self.assign_root_lit(implied_lit);
+                if let Some(proof) = self.proof.as_mut() {
+                    proof.delete_clause(&[lit.complementary(), implied_lit]);
+                    proof.delete_clause(&[lit, implied_lit]);
+                }
+            }
+            if self.deduce().is_some() {
+                return Conflict;
+            }
+        }
+
+        NoConflict
+    }
+
+    /// Assigns `lit` at level 1 and propagates it, then goes back to level 0. Returns the literals
+    /// it implies, `None` if it leads to a conflict. The assignments are silent.
+    fn probe(&mut self, lit: Literal) -> Option<LiteralVec> {
+        let start = self.trail.len();
+        self.silent_assignments = true;
+        self.trail.new_decision_level();
+        self.assign(lit, 1, None);
+
+        let conflict = self.deduce();
+        let implied_lits = self.trail.lits()[start + 1..].to_vec();
+        self.backtrack_to_level(0);
+        self.silent_assignments = false;
+
+        match conflict {
+            Some(_) => None,
+            None => Some(implied_lits),
+        }
+    }
+
+    /// Assigns at level 0 the unassigned `lit`, implied by the formula, and logs it as a unit clause.
+    fn assign_root_lit(&mut self, lit: Literal) {
+        if let Some(proof) = self.proof.as_mut() {
+            proof.add_clause(&[lit]);
+        }
+
+        self.assign(lit, 0, None);
+    }
+
+    /// Vivification: the literals of a clause are falsified one at a time and propagated. Those
+    /// found false are dropped, and the clause ends as soon as one is found true or a conflict
+    /// arises. Learnt clauses are vivified by increasing LBD, then original ones if enabled, each
+    /// once, for at most a tenth of the time spent since the last call. Must be called at level 0.
+    fn vivify_clauses(&mut self) -> Constant {
+        self.next_vivification = self.statistics.conflicts + VIVIFICATION_INTERVAL;
+        let start = Instant::now();
+        let deadline = start + start.duration_since(self.last_vivification) / VIVIFICATION_EFFORT;
+        self.last_vivification = start;
+
+        if !self.vivification {
+            return NoConflict;
+        }
+        if self.deduce().is_some() {
+            return Conflict;
+        }
+
+        let clauses = &self.clauses;
+        let mut candidates: Vec<ClauseRef> = clauses.iter()
+            .filter(|&clause_ref| clauses.is_learnt(clause_ref) && !clauses.is_vivified(clause_ref))
+            .collect();
+        candidates.sort_by_key(|&clause_ref| clauses.lbd(clause_ref));
+
+        if self.original_vivification {
+            candidates.extend(clauses.iter()
+                .filter(|&clause_ref| !clauses.is_learnt(clause_ref) && !clauses.is_vivified(clause_ref)));
+        }
+
+        for clause_ref in candidates {
+            if Instant::now() >= deadline {
+                break;
+            }
+
+            self.clauses.mark_vivified(clause_ref);
+            let lits = self.clauses.lits(clause_ref).to_vec();
+            if lits.iter().any(|&lit| self.trail.is_true(lit)) {
+                continue;
+            }
+
+            let vivified_lits = self.vivified(&lits);
+            if vivified_lits.len() == lits.len() {
+                continue;
+            }
+
+            self.statistics.vivified_clauses += 1;
+            if let Some(proof) = self.proof.as_mut() {
+                proof.add_clause(&vivified_lits);
+                proof.delete_clause(&lits);
+            }
+
+            let learnt = self.clauses.is_learnt(clause_ref);
+            let lbd = self.clauses.lbd(clause_ref).min(vivified_lits.len() as u32);
+            self.detach_clauses(&iter::once(clause_ref).collect());
+
+            match vivified_lits.len() {
+                1 => {
+                    self.assign(vivified_lits[0], 0, None);
+                    if self.deduce().is_some() {
+                        return Conflict;
+                    }
+                },
+                2 => { self.attach_binary_clause(&Clause::from_lits(&vivified_lits)); },
+                _ => {
+                    let clause = if learnt {
+                        Clause::new_asserting_clause(vivified_lits, lbd)
+                    } else {
+                        Clause::from_lits(&vivified_lits)
+                    };
+                    let vivified_clause_ref = self.attach_clause(&clause, learnt);
+                    self.clauses.mark_vivified(vivified_clause_ref);
+                },
+            }
+        }
+
+        NoConflict
+    }
+
+    /// Literals of a clause left by vivification: each literal of `lits` not falsified yet is
+    /// falsified at a new level, until one is found true or propagation leads to a conflict. Goes
+    /// back to level 0 afterwards. The assignments are silent.
+    fn vivified(&mut self, lits: &[Literal]) -> LiteralVec {
+        let mut vivified_lits = LiteralVec::with_capacity(lits.len());
+        self.silent_assignments = true;
+
+        for &lit in lits {
+            match self.trail.value(lit) {
+                Some(false) => continue,
+                Some(true) => {
+                    vivified_lits.push(lit);
+                    break;
+                },
+                None => vivified_lits.push(lit),
+            }
+
+            self.trail.new_decision_level();
+            let decision_level = self.current_decision_level();
+            self.assign(lit.complementary(), decision_level, None);
+
+            if self.deduce().is_some() {
+                break;
+            }
+        }
+
+        self.backtrack_to_level(0);
+        self.silent_assignments = false;
+
+        vivified_lits
+    }
+
+    /// Binary clauses, each given once.
+    fn binary_clauses(&self) -> Vec<[Literal; 2]> {
+        let mut binary_clauses = Vec::new();
+
+        for var in 1..=self.num_vars {
+            for &lit in &[Literal::from_var(var, true), Literal::from_var(var, false)] {
+                if let Some(other_lits) = self.binary_watches.get(lit.index()) {
+                    binary_clauses.extend(other_lits.iter()
+                        .filter(|other_lit| lit.index() < other_lit.index())
+                        .map(|&other_lit| [lit, other_lit]));
+                }
+            }
+        }
+
+        binary_clauses
+    }
+
+    /// Literals of an irredundant clause not falsified at level 0, `None` if it is satisfied there.
+    /// The proof gets the simplified clause in place of the original one.
+    fn root_simplified(&mut self, lits: &[Literal]) -> Option<LiteralVec> {
+        let is_satisfied = lits.iter().any(|&lit| self.trail.is_true(lit));
+        let trail = &self.trail;
+        let unassigned_lits: LiteralVec = lits.iter().cloned().filter(|&lit| !trail.is_false(lit)).collect();
 
-                lit = self.clauses[learnt_clause_id].first_watched_lit();
-                self.decision_stack.last_mut().unwrap().add_propagated_lit(lit, learnt_clause_id);
+        if let Some(proof) = self.proof.as_mut() {
+            if !is_satisfied && unassigned_lits.len() < lits.len() {
+                proof.add_clause(&unassigned_lits);
             }
+            if is_satisfied || unassigned_lits.len() < lits.len() {
+                proof.delete_clause(lits);
+            }
+        }
+
+        if is_satisfied {
+            None
+        } else {
+            Some(unassigned_lits)
+        }
+    }
+
+    /// Makes the eliminated `var` part of the formula again, along with the clauses it was
+    /// eliminated from.
+    fn restore_var(&mut self, var: u32) {
+        if !self.eliminated_vars.remove(&var) {
+            return;
+        }
+
+        self.decider.un_assign_lit(Literal::from_var(var, true));
+        for lits in self.elimination_stack.restore(var) {
+            self.add_clause(Clause::from_lits(&lits));
         }
     }
 
     fn print_status(&self) {
         println!("***************** STATUS ********************");
-        match self.decision_stack.last() {
-            Some(decision) => decision.print_status(),
-            None => println!("= No decisions"),
-        }
-        println!("- satisfied_clauses: {:?}", self.satisfied_clauses);
-        println!("- assigned_lits: {:?}", self.assigned_lits);
+        println!("= decision level: {}", self.current_decision_level());
+        println!("- trail: {:?}", self.trail.lits());
         println!("- watched_lits_per_clause: {}", self.format_watched_lits());
-        println!("- watched_lits: {:?}", self.watched_lit_to_clause);
-        println!("- unsatisfied clauses:\n{}", clause_vec_to_string(&self.clauses, &self.satisfied_clauses));
+        println!("- clauses:\n{}", self.clauses);
 
 
         println!("*********************************************");
     }
 
+    #[inline]
+    fn next_assumption(&self) -> Option<Literal> {
+        self.assumptions.get(self.current_decision_level() as usize).cloned()
+    }
+
+    /// Opens a new decision level for `assumption`. When it is already implied, the level is left
+    /// empty so that the level of every assumption still matches its position.
+    fn assume(&mut self, assumption: Literal) -> Option<Literal> {
+        self.trail.new_decision_level();
+
+        if self.trail.is_true(assumption) {
+            return None;
+        }
+
+        Some(assumption)
+    }
+
     fn decide_next_literal(&mut self) -> Option<Literal> {
-        let next_lit = self.decider.next_literal()?;
-        let next_lvl = self.current_decision_level() + 1;
+        let next_var = self.decider.next_variable()?;
+        let next_lit = self.phases.decision_lit(next_var);
+        self.statistics.decisions += 1;
 
-        self.decision_stack.push(Decision::from(next_lit, next_lvl));
+        self.trail.new_decision_level();
 
         Some(next_lit)
     }
 
-    fn deduce(&mut self, mut lit: Literal) -> Constant {
+    /// Assigns `lit` at `level`, the decision level of the decision literal or of the clause
+    /// implying it. The clauses it satisfies and its watches are visited by the next call to
+    /// `deduce`.
+    fn assign(&mut self, lit: Literal, level: u32, reason: Option<Reason>) {
+        self.trail.assign(lit, level, reason);
+        if !self.silent_assignments || level == 0 {
+            self.decider.assign_lit(lit);
+            self.phases.save(lit);
+        }
+        self.statistics.propagations += 1;
+    }
+
+    /// Unit propagation of the literals of the trail not propagated yet, through binary clauses
+    /// first, then through the XOR constraints once the clauses are done. Returns the clause
+    /// falsified by the assignments if there is a conflict.
+    fn deduce(&mut self) -> Option<Reason> {
         if self.verbose {
             self.print_status();
         }
 
-        let mut decision = self.decision_stack.pop().unwrap();
-        let mut propagated_lits = LiteralSet::default();
-
         loop {
-            self.decider.assign_lit(lit);
-            self.assigned_lits.insert(lit);
+            if let Some(conflict) = self.propagate_clauses() {
+                return Some(conflict);
+            }
+            if let Some(conflict) = self.propagate_xors() {
+                return Some(conflict);
+            }
+            if self.propagated == self.trail.len() {
+                break;
+            }
+        }
 
-            // Satisfy clauses
-            if let Some(clause_ids) = self.lit_to_clause.get(&lit).map(|c| c.as_slice()) {
-                for &clause_id in clause_ids {
-                    if self.satisfied_clauses.insert(clause_id) {
-                        decision.add_satisfied_clause(clause_id);
-                    }
-                }
+        if self.verbose {
+            println!("after");
+            self.print_status();
+        }
+
+        None
+    }
+
+    fn propagate_clauses(&mut self) -> Option<Reason> {
+        while self.propagated < self.trail.len() {
+            let lit = self.trail.lits()[self.propagated];
+            self.propagated += 1;
+
+            let false_lit = lit.complementary();
+            if let Some(conflict) = self.propagate_binary_clauses(false_lit) {
+                self.propagated -= 1;
+                return Some(conflict);
             }
 
-            // Strengthen clauses
-            let mut new_lits_to_watch = Vec::new();
-            let complementary = lit.complementary();
-            if let Some(clause_ids) = self.watched_lit_to_clause.get(&complementary) {
-                for &clause_id in clause_ids.difference(&self.satisfied_clauses) {
-                    let clause_ref: &mut Clause = &mut self.clauses[clause_id];
+            let mut watches = match self.watches.get_mut(false_lit.index()) {
+                Some(watches) => mem::take(watches),
+                None => continue,
+            };
+
+            // Watches kept in the list of false_lit are moved to the front of watches
+            let mut kept_watches = 0;
+            let mut conflict = None;
 
-                    let next_watched_lit = clause_ref.strengthen(complementary, &self.assigned_lits);
+            for watch_position in 0..watches.len() {
+                let watch = watches[watch_position];
 
-                    if clause_ref.is_unary() {
-                        if let Conflict = decision.add_propagated_lit(clause_ref.first_watched_lit(), clause_id) {
-                            self.decision_stack.push(decision);
-                            return Conflict;
+                if conflict.is_some() || self.trail.is_true(watch.blocker) {
+                    watches[kept_watches] = watch;
+                    kept_watches += 1;
+                    continue;
+                }
+
+                let clause_ref = watch.clause_ref;
+                let lits = self.clauses.lits_mut(clause_ref);
+
+                // The false literal goes second
+                if lits[0] == false_lit {
+                    lits.swap(0, 1);
+                }
+
+                let first_lit = lits[0];
+                if first_lit != watch.blocker && self.trail.is_true(first_lit) {
+                    watches[kept_watches] = Watch { clause_ref, blocker: first_lit };
+                    kept_watches += 1;
+                    continue;
+                }
+
+                let trail = &self.trail;
+                if let Some(position) = (2..lits.len()).find(|&position| !trail.is_false(lits[position])) {
+                    lits.swap(1, position);
+                    let new_watched_lit = lits[1];
+                    self.watch_lit(new_watched_lit, Watch { clause_ref, blocker: first_lit });
+                    continue;
+                }
+
+                // The clause is unit or falsified
+                if self.trail.is_false(first_lit) {
+                    watches[kept_watches] = Watch { clause_ref, blocker: first_lit };
+                    kept_watches += 1;
+                    conflict = Some(Reason::Clause(clause_ref));
+                    continue;
+                }
+
+                match self.chrono_threshold {
+                    Some(_) => {
+                        // Literals are assigned out of order: the one falsified at the highest
+                        // level must be watched, for the clause to be visited again when
+                        // backtracking frees it
+                        let trail = &self.trail;
+                        let lits = self.clauses.lits_mut(clause_ref);
+                        let highest_position = (1..lits.len())
+                            .max_by_key(|&position| trail.level(lits[position].var()))
+                            .unwrap();
+                        let level = self.trail.level(lits[highest_position].var());
+
+                        if self.trail.level(false_lit.var()) == level {
+                            watches[kept_watches] = Watch { clause_ref, blocker: first_lit };
+                            kept_watches += 1;
+                        } else {
+                            lits.swap(1, highest_position);
+                            let new_watched_lit = lits[1];
+                            self.watch_lit(new_watched_lit, Watch { clause_ref, blocker: first_lit });
                         }
-                        propagated_lits.insert(clause_ref.first_watched_lit());
-                    } else {
-                        new_lits_to_watch.push((clause_id, next_watched_lit.unwrap()));
-                    }
+
+                        self.assign(first_lit, level, Some(Reason::Clause(clause_ref)));
+                    },
+                    None => {
+                        watches[kept_watches] = Watch { clause_ref, blocker: first_lit };
+                        kept_watches += 1;
+
+                        let level = self.current_decision_level();
+                        self.assign(first_lit, level, Some(Reason::Clause(clause_ref)));
+                    },
                 }
             }
 
-            new_lits_to_watch.into_iter().for_each(|(clause_id, lit)| {
-                self.add_watched_lit(clause_id, lit);
-            });
+            watches.truncate(kept_watches);
+            self.watches[false_lit.index()] = watches;
 
-            self.watched_lit_to_clause.entry(complementary)
-                .and_modify(|clause_ids| clause_ids.clear())
-                .or_insert_with(|| FnvHashSet::default());
+            if conflict.is_some() {
+                // The watches of lit are visited again if it is still assigned after backtracking
+                self.propagated -= 1;
+                return conflict;
+            }
+        }
 
-            if propagated_lits.is_empty() {
-                break;
+        None
+    }
+
+    /// Runs the Gauss-Jordan elimination when variables of the XOR constraints were assigned since
+    /// the last time, and assigns the literals it implies. Returns the clause explaining the
+    /// conflict if there is one.
+    fn propagate_xors(&mut self) -> Option<Reason> {
+        let gauss_matrix = self.gauss_matrix.as_ref()?;
+        let is_assigned = self.trail.lits()[self.xor_propagated..].iter().any(|lit| gauss_matrix.contains(lit.var()));
+        self.xor_propagated = self.trail.len();
+        if !is_assigned {
+            return None;
+        }
+
+        self.release_xor_reasons();
+
+        let explanations = match self.gauss_matrix.as_mut().unwrap().propagate(&self.trail) {
+            Ok(explanations) => explanations,
+            Err(lits) => return Some(self.add_xor_reason(lits)),
+        };
+
+        for lits in explanations {
+            // Several rows may imply the same variable
+            match self.trail.value(lits[0]) {
+                Some(true) => continue,
+                Some(false) => return Some(self.add_xor_reason(lits)),
+                None => {},
             }
 
-            // No conflicts yet, prepare next lit to propagate
-            lit = propagated_lits.iter().next().cloned().unwrap();
-            propagated_lits.remove(&lit);
+            let implied_lit = lits[0];
+            let reason = self.add_xor_reason(lits);
+            let level = match self.chrono_threshold {
+                Some(_) => self.implication_level(reason),
+                None => self.current_decision_level(),
+            };
+            self.assign(implied_lit, level, Some(reason));
         }
 
-        self.decision_stack.push(decision);
+        None
+    }
 
-        if self.verbose {
-            println!("after");
-            self.print_status();
+    /// Stores a clause explaining a deduction of the XOR constraints, so that it can be a reason.
+    /// It is not watched, and it is deleted once it is not a reason anymore.
+    fn add_xor_reason(&mut self, lits: LiteralVec) -> Reason {
+        let clause_ref = self.clauses.add_xor_reason(&lits);
+        self.xor_reasons.push(clause_ref);
+
+        Reason::Clause(clause_ref)
+    }
+
+    /// Deletes the clauses explaining deductions of the XOR constraints that were backtracked. They
+    /// are not watched, nor counted among the deleted clauses.
+    fn release_xor_reasons(&mut self) {
+        let clauses = &mut self.clauses;
+        let trail = &self.trail;
+
+        self.xor_reasons.retain(|&clause_ref| {
+            let lit = clauses.lits(clause_ref)[0];
+            if trail.is_true(lit) && trail.reason(lit.var()) == Some(Reason::Clause(clause_ref)) {
+                return true;
+            }
+
+            clauses.delete(clause_ref);
+            false
+        });
+    }
+
+    /// Assigns the literals implied by the binary clauses of `false_lit`. Returns the binary clause
+    /// falsified by the assignments if there is a conflict.
+    fn propagate_binary_clauses(&mut self, false_lit: Literal) -> Option<Reason> {
+        let implied_lits = match self.binary_watches.get_mut(false_lit.index()) {
+            Some(implied_lits) => mem::take(implied_lits),
+            None => return None,
+        };
+        let mut conflict = None;
+
+        for &implied_lit in &implied_lits {
+            if self.trail.is_true(implied_lit) {
+                continue;
+            }
+
+            let reason = Reason::Binary([implied_lit, false_lit]);
+            if self.trail.is_false(implied_lit) {
+                conflict = Some(reason);
+                break;
+            }
+
+            let level = match self.chrono_threshold {
+                Some(_) => self.trail.level(false_lit.var()),
+                None => self.current_decision_level(),
+            };
+            self.assign(implied_lit, level, Some(reason));
         }
 
-        NoConflict
+        self.binary_watches[false_lit.index()] = implied_lits;
+
+        conflict
+    }
+
+    /// Level at which the unit clause `reason` implies its first literal: the highest level among
+    /// its other, falsified, literals.
+    fn implication_level(&self, reason: Reason) -> u32 {
+        reason.lits(&self.clauses)[1..].iter()
+            .map(|lit| self.trail.level(lit.var()))
+            .max()
+            .unwrap_or(0)
     }
 
     #[inline]
     fn current_decision_level(&self) -> u32 {
-        match self.decision_stack.last() {
-            Some(decision) => decision.lvl(),
-            None => 0,
+        self.trail.decision_level()
+    }
+
+    /// Backtracks out of the conflict on the `conflict` clause, learning a clause from it when
+    /// needed, and assigns the literal it then implies. Returns false if the formula is UNSAT.
+    fn resolve_conflict(&mut self, conflict: Reason) -> bool {
+        let conflict_levels: Vec<u32> = conflict.lits(&self.clauses).iter()
+            .map(|lit| self.trail.level(lit.var()))
+            .collect();
+        let conflict_level = conflict_levels.iter().cloned().max().unwrap_or(0);
+
+        if conflict_level == 0 {
+            return false;
+        }
+
+        if self.phases.tracks_conflicts() {
+            let trail = &self.trail;
+            let conflict_free_lits = trail.lits().iter().cloned().filter(|lit| trail.level(lit.var()) < conflict_level);
+            let conflict_free_size = conflict_free_lits.clone().count();
+            self.phases.on_conflict(conflict_free_lits, conflict_free_size);
+        }
+
+        // With chronological backtracking, the conflicting clause may be unit at a lower level:
+        // the literal it implies was missed. Otherwise, the conflict is analyzed at its level.
+        if conflict_levels.iter().filter(|&&level| level == conflict_level).count() == 1 {
+            self.backtrack_to_level(conflict_level - 1);
+            let reason = self.rewatch_conflict(conflict);
+
+            let implied_lit = reason.lits(&self.clauses)[0];
+            let implication_level = self.implication_level(reason);
+            self.assign(implied_lit, implication_level, Some(reason));
+
+            return true;
+        }
+
+        self.backtrack_to_level(conflict_level);
+        let (learnt_clause, backjump_level) = self.analyze_conflict(conflict);
+
+        let is_chronological = self.chrono_threshold
+            .is_some_and(|threshold| conflict_level - backjump_level > threshold);
+
+        if is_chronological {
+            self.backtrack_to_level(conflict_level - 1);
+            self.statistics.chrono_backtracks += 1;
+        } else {
+            // Everything above the level where the learnt clause becomes unit is undone at once
+            self.backtrack_to_level(backjump_level);
         }
+        self.rewatch_conflict(conflict);
+
+        let asserting_lit = learnt_clause.lits(&self.clauses)[0];
+        self.assign(asserting_lit, backjump_level, Some(learnt_clause));
+
+        true
+    }
+
+    /// Watches again the clause falsified by a conflict, now that backtracking freed some of its
+    /// literals, and returns it with the best literal to watch first.
+    fn rewatch_conflict(&mut self, conflict: Reason) -> Reason {
+        match conflict {
+            Reason::Clause(clause_ref) => self.rewatch_clause(clause_ref),
+            Reason::Binary([lit_a, lit_b]) if self.trail.is_false(lit_a) && !self.trail.is_false(lit_b) => {
+                return Reason::Binary([lit_b, lit_a]);
+            },
+            Reason::Binary(_) => {},
+        }
+
+        conflict
     }
 
-    fn analyze_conflict(&mut self) -> ClauseId {
-        let asserting_clause = learn_from_conflict(self.decision_stack.last().unwrap(), &self.clauses);
-        let clause_id = self.add_clause(asserting_clause.clone());
+    fn rewatch_clause(&mut self, clause_ref: ClauseRef) {
+        let lits = self.clauses.lits(clause_ref);
+        if lits.len() < 2 {
+            return;
+        }
 
-        self.learnt_clauses.insert(clause_id);
+        let watched_lits = [lits[0], lits[1]];
+        sort_watched_lits(&self.trail, self.clauses.lits_mut(clause_ref));
 
-        clause_id
+        // Explanations of the XOR constraints are not watched
+        let lits = self.clauses.lits(clause_ref);
+        if !self.clauses.is_xor_reason(clause_ref) && (!watched_lits.contains(&lits[0]) || !watched_lits.contains(&lits[1])) {
+            watched_lits.iter().for_each(|&lit| self.unwatch_lit(lit, clause_ref));
+            self.watch_clause(clause_ref);
+        }
     }
 
-    fn backtrack(&mut self, conflict_clause_id: ClauseId) -> bool {
-        loop {
-            let last_decision = self.decision_stack.pop().unwrap();
-            if last_decision.lit() == Literal::non_existent() {
-                return false;
+    /// Learns a clause from the `conflict` clause, returning it along with its backjump level.
+    /// Learnt binary clauses are never deleted.
+    fn analyze_conflict(&mut self, conflict: Reason) -> (Reason, u32) {
+        let learnt_clause = learn_from_conflict(conflict, &self.trail, &self.clauses);
+        let asserting_clause = learnt_clause.clause;
+        self.statistics.learnt_clauses += 1;
+
+        self.restart_strategy.on_conflict(asserting_clause.lbd());
+        self.decider.on_conflict(&learnt_clause.involved_vars);
+
+        if let Some(proof) = self.proof.as_mut() {
+            proof.add_clause(asserting_clause.lits());
+        }
+
+        if asserting_clause.lits().len() == 2 {
+            let reason = self.attach_binary_clause(&asserting_clause);
+            self.bump_clause_activities(learnt_clause.antecedents.into_iter());
+
+            return (reason, learnt_clause.backjump_level);
+        }
+
+        let clause_ref = self.attach_clause(&asserting_clause, true);
+        self.bump_clause_activities(learnt_clause.antecedents.into_iter().chain(Some(clause_ref)));
+
+        (Reason::Clause(clause_ref), learnt_clause.backjump_level)
+    }
+
+    /// Bumps the activity of the learnt clauses involved in a conflict, then decays every activity
+    /// by growing the increment used for the next bumps.
+    fn bump_clause_activities<I: Iterator<Item=ClauseRef>>(&mut self, clause_refs: I) {
+        for clause_ref in clause_refs {
+            if !self.clauses.is_learnt(clause_ref) {
+                continue;
             }
 
-            // undo lit assignments
-            last_decision.propagated_lits_iter().for_each(|propagated_lit| {
-                self.assigned_lits.remove(&propagated_lit);
-                self.decider.un_assign_lit(propagated_lit);
-            });
+            self.clauses.bump_activity(clause_ref, self.clause_activity_increment);
 
-            self.assigned_lits.remove(&last_decision.lit());
-            self.decider.un_assign_lit(last_decision.lit());
+            if self.clauses.activity(clause_ref) > CLAUSE_ACTIVITY_LIMIT {
+                let learnt_clause_refs: Vec<ClauseRef> = self.clauses.iter()
+                    .filter(|&learnt_clause_ref| self.clauses.is_learnt(learnt_clause_ref))
+                    .collect();
+                for learnt_clause_ref in learnt_clause_refs {
+                    self.clauses.scale_activity(learnt_clause_ref, 1.0 / CLAUSE_ACTIVITY_LIMIT);
+                }
+                self.clause_activity_increment /= CLAUSE_ACTIVITY_LIMIT;
+            }
+        }
 
-            // undo satisfied clauses
-            last_decision.satisfied_clauses().iter().for_each(|clause_id| {
-                self.satisfied_clauses.remove(clause_id);
-            });
+        self.clause_activity_increment /= CLAUSE_ACTIVITY_DECAY;
+    }
+
+    /// Deletes the least useful half of the learnt clauses, the ones with the highest LBD first and,
+    /// among those, the least active ones. Glue clauses and clauses implying a current assignment
+    /// are kept. The clause database is then compacted if needed.
+    fn reduce_learnt_clauses(&mut self) {
+        let trail = &self.trail;
+        let locked_clauses: FnvHashSet<ClauseRef> = trail.lits().iter()
+            .filter_map(|lit| trail.reason(lit.var()).and_then(|reason| reason.clause_ref()))
+            .collect();
+
+        let clauses = &self.clauses;
+        let mut candidates: Vec<ClauseRef> = clauses.iter()
+            .filter(|&clause_ref| clauses.is_learnt(clause_ref) && clauses.lbd(clause_ref) > GLUE_LBD)
+            .filter(|clause_ref| !locked_clauses.contains(clause_ref))
+            .collect();
+
+        candidates.sort_by(|&a, &b| {
+            clauses.lbd(b).cmp(&clauses.lbd(a))
+                .then(clauses.activity(a).partial_cmp(&clauses.activity(b)).unwrap())
+        });
+        candidates.truncate(candidates.len() / 2);
+
+        self.delete_clauses(&candidates.into_iter().collect());
+
+        if self.clauses.garbage_fraction() > MAX_GARBAGE_FRACTION {
+            self.collect_garbage();
+        }
+
+        self.reduction_interval += REDUCTION_INTERVAL_INCREMENT;
+        self.next_reduction = self.statistics.conflicts + self.reduction_interval;
+    }
+
+    /// Removes clauses from the clause database. Their space is reclaimed by the next garbage
+    /// collection.
+    fn delete_clauses(&mut self, clause_refs: &FnvHashSet<ClauseRef>) {
+        if let Some(proof) = self.proof.as_mut() {
+            let clauses = &self.clauses;
+            clause_refs.iter().for_each(|&clause_ref| proof.delete_clause(clauses.lits(clause_ref)));
+        }
+
+        self.detach_clauses(clause_refs);
+    }
+
+    /// Removes clauses from the clause database, without logging their deletion.
+    fn detach_clauses(&mut self, clause_refs: &FnvHashSet<ClauseRef>) {
+        let mut watched_lits = LiteralSet::default();
+
+        for &clause_ref in clause_refs {
+            watched_lits.extend(self.clauses.lits(clause_ref).iter().take(2).cloned());
+
+            self.clauses.delete(clause_ref);
+            self.statistics.deleted_clauses += 1;
+        }
+
+        for lit in watched_lits {
+            if let Some(watches) = self.watches.get_mut(lit.index()) {
+                watches.retain(|watch| !clause_refs.contains(&watch.clause_ref));
+            }
+        }
+    }
 
-            // re-sync un strengthen clauses
-            last_decision.unary_clauses().iter().for_each(|&implying_clause_id| {
-                self.clauses[implying_clause_id].un_strengthen(&self.assigned_lits);
+    /// Compacts the clause database, then updates the references the watches and the reasons hold
+    /// to the clauses it moved.
+    fn collect_garbage(&mut self) {
+        let relocation = self.clauses.collect_garbage();
 
-                let new_watched_lit = self.clauses[implying_clause_id].second_watched_lit().unwrap();
-                self.add_watched_lit(implying_clause_id, new_watched_lit);
+        for watches in self.watches.iter_mut() {
+            watches.retain_mut(|watch| match relocation.get(watch.clause_ref) {
+                Some(clause_ref) => {
+                    watch.clause_ref = clause_ref;
+                    true
+                },
+                None => false,
             });
+        }
 
-            // If current decision will trigger UP on the learnt clause, stop backtracking.
-            let current_decision_lit = self.decision_stack.last().unwrap().lit();
-            if self.clauses[conflict_clause_id].can_be_strengthen_by(current_decision_lit) {
-                return true;
+        self.trail.relocate_reasons(&relocation);
+        self.xor_reasons = self.xor_reasons.iter()
+            .filter_map(|&clause_ref| relocation.get(clause_ref))
+            .collect();
+        self.unit_clauses = self.unit_clauses.iter()
+            .filter_map(|reason| reason.relocate(&relocation))
+            .collect();
+    }
+
+    /// Goes back to level 0, keeping learnt clauses and decider scores.
+    fn restart(&mut self) {
+        self.backtrack_to_level(0);
+        self.restart_strategy.on_restart();
+        self.phases.on_restart();
+        self.statistics.restarts += 1;
+    }
+
+    fn backtrack_to_level(&mut self, level: u32) {
+        if self.current_decision_level() <= level {
+            return;
+        }
+
+        // Literals of the lower levels are propagated again if they were assigned out of order
+        self.propagated = self.propagated.min(self.trail.limit(level + 1));
+        self.xor_propagated = self.xor_propagated.min(self.trail.limit(level + 1));
+
+        // Silent assignments are all above level 0, which the decider never saw
+        for unassigned_lit in self.trail.backtrack(level) {
+            if !self.silent_assignments {
+                self.decider.un_assign_lit(unassigned_lit);
             }
         }
     }
 
     fn format_watched_lits(&self) -> String {
-        let result: FnvHashMap<ClauseId, [Literal; 2]> = self.clauses.iter().enumerate()
-            .filter(|(id, _clause)| !self.satisfied_clauses.contains(id))
-            .map(|(id, clause)| {
-                (id, [clause.first_watched_lit(), clause.second_watched_lit().unwrap_or(Literal::non_existent())])
+        let result: FnvHashMap<ClauseRef, &[Literal]> = self.clauses.iter()
+            .map(|clause_ref| {
+                let lits = self.clauses.lits(clause_ref);
+                (clause_ref, &lits[..lits.len().min(2)])
             })
             .collect();
 
         format!("{:?}", result)
     }
 }
+
+/// Moves to the first two positions of `lits` the literals that are the best to watch: the ones
+/// not falsified, then the ones falsified at the highest levels. Those already in place stay there
+/// on ties.
+fn sort_watched_lits(trail: &Trail, lits: &mut [Literal]) {
+    let watch_priority = |lit: Literal| {
+        if trail.is_false(lit) {
+            trail.level(lit.var())
+        } else {
+            u32::MAX
+        }
+    };
+
+    for position in 0..lits.len().min(2) {
+        let mut best_position = position;
+
+        for candidate_position in position + 1..lits.len() {
+            if watch_priority(lits[candidate_position]) > watch_priority(lits[best_position]) {
+                best_position = candidate_position;
+            }
+        }
+
+        lits.swap(position, best_position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use checker::check_drat;
+    use model::ClauseVec;
+    use proof::ProofFormat;
+    use std::cell::RefCell;
+    use std::io;
+    use std::io::Write;
+    use std::rc::Rc;
+
+    fn lits(dimacs_lits: &[i32]) -> LiteralVec {
+        dimacs_lits.iter().map(|&lit| Literal::from(lit)).collect()
+    }
+
+    fn satisfies(model: &LiteralSet, dimacs_clauses: &[&[i32]]) -> bool {
+        dimacs_clauses.iter().all(|clause| clause.iter().any(|&lit| model.contains(&Literal::from(lit))))
+    }
+
+    /// Literals of an XOR constraint over `1..=num_vars`, negating every third variable.
+    fn long_xor_lits(num_vars: i32) -> Vec<i32> {
+        (1..=num_vars).map(|var| if var % 3 == 0 { -var } else { var }).collect()
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn solves_under_assumptions_then_under_conflicting_ones() {
+        let dimacs_clauses: &[&[i32]] = &[&[-1, 2], &[-2, 3], &[1, 4]];
+        let mut solver = Solver::new();
+        dimacs_clauses.iter().for_each(|clause| solver.add_clause_from_lits(clause));
+
+        let model = solver.solve_with_assumptions(&lits(&[1])).expect("SAT under 1");
+        assert!(satisfies(&model, dimacs_clauses));
+        assert!(model.contains(&Literal::from(3)));
+
+        assert_eq!(solver.solve_with_assumptions(&lits(&[1, -3])), None);
+        assert!(solver.solve_with_assumptions(&lits(&[-3])).is_some());
+        assert!(solver.solve().is_some());
+    }
+
+    #[test]
+    fn solves_with_clauses_added_between_calls() {
+        let mut solver = Solver::new();
+        solver.add_clause_from_lits(&[1, 2]);
+        assert!(solver.solve_with_assumptions(&lits(&[-1])).is_some());
+        assert_eq!(solver.value(2), Some(true));
+
+        solver.add_clause_from_lits(&[-2, 3]);
+        solver.add_clause_from_lits(&[-3, 4]);
+        assert_eq!(solver.solve_with_assumptions(&lits(&[-1, -4])), None);
+
+        let model = solver.solve_with_assumptions(&lits(&[-4])).expect("SAT under -4");
+        assert!(satisfies(&model, &[&[1, 2], &[-2, 3], &[-3, 4]]));
+        assert_eq!(solver.value(1), Some(true));
+
+        solver.add_clause_from_lits(&[-1]);
+        assert_eq!(solver.solve_with_assumptions(&lits(&[-4])), None);
+        assert!(solver.solve().is_some());
+        assert_eq!(solver.value(4), Some(true));
+    }
+
+    #[test]
+    fn failed_assumptions_are_unsat_on_their_own() {
+        let mut solver = Solver::new();
+        [&[-1, -2][..], &[-3, 5], &[-5, -4]].iter().for_each(|clause| solver.add_clause_from_lits(clause));
+        let assumptions = lits(&[6, 3, 7, 4, 1]);
+
+        assert_eq!(solver.solve_with_assumptions(&assumptions), None);
+        let failed_assumptions: LiteralVec = solver.failed_assumptions().iter().cloned().collect();
+        assert!(failed_assumptions.iter().all(|lit| assumptions.contains(lit)));
+        assert_eq!(solver.failed_assumptions(), &lits(&[3, 4]).into_iter().collect());
+
+        assert_eq!(solver.solve_with_assumptions(&failed_assumptions), None);
+        assert!(solver.solve().is_some());
+        assert!(solver.failed_assumptions().is_empty());
+    }
+
+    #[test]
+    fn no_assumption_fails_when_the_formula_is_unsat() {
+        let mut solver = Solver::new();
+        [&[1, 2][..], &[1, -2], &[-1, 2], &[-1, -2]].iter().for_each(|clause| solver.add_clause_from_lits(clause));
+
+        assert_eq!(solver.solve_with_assumptions(&lits(&[3])), None);
+        assert!(solver.failed_assumptions().is_empty());
+    }
+
+    #[test]
+    fn long_xors_are_solved_as_clauses() {
+        let mut solver = Solver::new();
+        solver.set_gaussian_elimination(false);
+        solver.add_xor_from_lits(&long_xor_lits(70));
+        solver.add_clause_from_lits(&[1, 2]);
+
+        let model = solver.solve().expect("SAT");
+        assert!(Xor::from_lits(&lits(&long_xor_lits(70))).is_satisfied_by(&model));
+        assert!(solver.num_vars() > 70);
+
+        let mut flipped_lits = long_xor_lits(70);
+        flipped_lits[0] = -flipped_lits[0];
+        solver.add_xor_from_lits(&flipped_lits);
+        assert_eq!(solver.solve(), None);
+    }
+
+    #[test]
+    fn long_xors_are_written_to_proofs_as_clauses() {
+        let xors = [Xor::from_lits(&lits(&long_xor_lits(70))), Xor::from_lits(&lits(&long_xor_lits(70)[1..]))];
+        let buffer = SharedBuffer::default();
+        let mut solver = Solver::new();
+        solver.set_proof(Proof::new(Box::new(buffer.clone()), ProofFormat::Text));
+        xors.iter().for_each(|xor| solver.add_xor(xor.clone()));
+        // The two constraints leave x1 false
+        solver.add_clause_from_lits(&[1]);
+
+        assert_eq!(solver.solve(), None);
+        solver.take_proof().unwrap().finish().unwrap();
+
+        let mut num_vars = 70;
+        let mut clauses: ClauseVec = xors.iter()
+            .flat_map(|xor| xor.clauses(&mut num_vars))
+            .map(|lits| Clause::from_lits(&lits))
+            .collect();
+        clauses.push(Clause::from_lits(&lits(&[1])));
+        assert_eq!(num_vars, solver.num_vars());
+        assert_eq!(check_drat(&clauses, num_vars, &buffer.0.borrow()), Ok(()));
+    }
+}
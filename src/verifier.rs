@@ -0,0 +1,32 @@
+use model::ClauseSet;
+use model::LiteralSet;
+use model::Xor;
+
+/// Checks `model` against the original `clauses`, independently from the solver state: it must not
+/// contain both a literal and its complementary, it must assign every variable occurring in the
+/// clauses, and it must satisfy each one of them.
+pub fn verify_model(clauses: &ClauseSet, model: &LiteralSet) -> Result<(), String> {
+    if let Some(lit) = model.iter().find(|lit| model.contains(&lit.complementary())) {
+        return Err(format!("the model is contradictory, it contains both {} and {}", lit, lit.complementary()));
+    }
+
+    let unassigned_lit = clauses.iter()
+        .flat_map(|clause| clause.lits().iter())
+        .find(|lit| !model.contains(lit) && !model.contains(&lit.complementary()));
+    if let Some(lit) = unassigned_lit {
+        return Err(format!("the model is partial, variable {} is not assigned", lit.var()));
+    }
+
+    match clauses.iter().find(|clause| !clause.lits().iter().any(|lit| model.contains(lit))) {
+        Some(clause) => Err(format!("the model does not satisfy clause {}", clause)),
+        None => Ok(()),
+    }
+}
+
+/// Checks `model`, already verified against the clauses, against the original XOR constraints.
+pub fn verify_xors(xors: &[Xor], model: &LiteralSet) -> Result<(), String> {
+    match xors.iter().find(|xor| !xor.is_satisfied_by(model)) {
+        Some(xor) => Err(format!("the model does not satisfy the XOR constraint of variables {:?}", xor.vars())),
+        None => Ok(()),
+    }
+}